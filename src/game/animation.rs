@@ -10,13 +10,24 @@ use super::{
 
 /// Animations and particles
 pub(super) fn plugin(app: &mut App) {
+    app.register_type::<SkidMark>();
     app.add_systems(Update, animate_player.run_if(in_state(Screen::Playing)));
+    app.add_systems(
+        FixedUpdate,
+        (spawn_skid_marks, fade_skid_marks).run_if(in_state(Screen::Playing)),
+    );
 }
 
+const SKID_MARK_MIN_SLIP: f32 = 0.6;
+const SKID_MARK_COLOR: Color = Color::srgba(0.15, 0.15, 0.15, 0.6);
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct SkidMark(pub Timer);
+
 fn animate_player(
     mut query: Query<
         (
-            &Transform,
             &mut Animation,
             &PlayerMovement,
             &PlayerController,
@@ -25,27 +36,71 @@ fn animate_player(
         With<Player>,
     >,
 ) {
-    if query.is_empty() {
-        return;
-    }
+    for (mut animation, movement, controller, velocity) in query.iter_mut() {
+        if controller.damn {
+            continue;
+        }
 
-    let (transform, mut animation, movement, controller, velocity) = query.single_mut();
+        if animation.tag != Some("launch-letter".into()) {
+            if velocity.0.length() < 0.2 && velocity.0.length() > -0.2 {
+                animation.play("pause", AnimationRepeat::Loop);
+            } else if movement.friction >= 12. || movement.drifting {
+                animation.play("brake", AnimationRepeat::Loop);
+            }
 
-    if controller.damn {
-        return;
+            if velocity.0.length() > 2. {
+                animation.play("ride-fast", AnimationRepeat::Loop);
+            } else if velocity.0.length() > 0.2 {
+                animation.play("ride", AnimationRepeat::Loop);
+            }
+        }
     }
+}
 
-    if animation.tag != Some("launch-letter".into()) {
-        if velocity.0.length() < 0.2 && velocity.0.length() > -0.2 {
-            animation.play("pause", AnimationRepeat::Loop);
-        } else if movement.friction >= 12. {
-            animation.play("brake", AnimationRepeat::Loop);
+fn spawn_skid_marks(
+    mut commands: Commands,
+    query: Query<(&Transform, &PlayerMovement), With<Player>>,
+) {
+    for (transform, movement) in query.iter() {
+        if !movement.drifting && movement.slip < SKID_MARK_MIN_SLIP {
+            continue;
         }
 
-        if velocity.0.length() > 2. {
-            animation.play("ride-fast", AnimationRepeat::Loop);
-        } else if velocity.0.length() > 0.2 {
-            animation.play("ride", AnimationRepeat::Loop);
+        // rear wheel of the bike
+        let position = transform.translation.xy() - (transform.rotation * Vec3::Y).xy() * 4.;
+
+        commands.spawn((
+            Name::new("Skid Mark"),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: SKID_MARK_COLOR,
+                    custom_size: Some(Vec2::splat(2.)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(0.005))
+                    .with_rotation(transform.rotation),
+                ..default()
+            },
+            SkidMark(Timer::from_seconds(4., TimerMode::Once)),
+            StateScoped(Screen::Playing),
+        ));
+    }
+}
+
+fn fade_skid_marks(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut SkidMark)>,
+) {
+    for (entity, mut sprite, mut skid_mark) in query.iter_mut() {
+        skid_mark.0.tick(time.delta());
+
+        if skid_mark.0.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite
+                .color
+                .set_alpha(SKID_MARK_COLOR.alpha() * skid_mark.0.fraction_remaining());
         }
     }
 }
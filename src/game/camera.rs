@@ -4,10 +4,10 @@ use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::screen::Screen;
 
-use super::circuit::{Circuit, CircuitDirection, CircuitOrientation};
+use super::{circuit::Circuit, spawn::player::PlayerSlot};
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<CameraTarget>();
+    app.register_type::<(CameraTarget, PlayerCamera)>();
     app.add_systems(FixedUpdate, follow_target.run_if(in_state(Screen::Playing)));
     app.add_systems(Update, resizing);
 }
@@ -18,36 +18,29 @@ pub struct MainCamera;
 #[derive(Component, Reflect)]
 pub struct CameraTarget;
 
+/// Camera of another local player in split-screen, the main camera follows the first one
+#[derive(Component, Reflect)]
+pub struct PlayerCamera(pub usize);
+
 fn follow_target(
     time: Res<Time>,
-    mut camera_query: Query<&mut Transform, With<MainCamera>>,
-    target_query: Query<&Transform, (With<CameraTarget>, Without<MainCamera>)>,
-    circuit: Res<Circuit>,
+    mut camera_query: Query<
+        (&mut Transform, Option<&PlayerCamera>),
+        (With<Camera>, Or<(With<MainCamera>, With<PlayerCamera>)>),
+    >,
+    target_query: Query<(&Transform, &Circuit, &PlayerSlot), (With<CameraTarget>, Without<Camera>)>,
 ) {
-    if let Ok(mut camera_transform) = camera_query.get_single_mut() {
-        if let Ok(target_transform) = target_query.get_single() {
-            let mut offset = if circuit.in_turn {
-                Vec3::ZERO
-            } else if circuit.current_orientation == CircuitOrientation::Vertical {
-                Vec3::Y * 50.
-            } else if circuit.current_orientation == CircuitOrientation::Horizontal {
-                Vec3::X * 50.
-            } else {
-                Vec3::ZERO
-            };
+    for (mut camera_transform, player_camera) in camera_query.iter_mut() {
+        let slot = player_camera.map_or(0, |camera| camera.0);
+        let target = target_query
+            .iter()
+            .find(|(_, _, target_slot)| target_slot.0 == slot);
 
-            offset = if circuit.direction == CircuitDirection::Clockwise {
-                offset
-            } else {
-                -offset
-            };
-
-            offset = if circuit.turn_count >= 4 {
-                offset
-            } else if circuit.turn_count >= 2 || circuit.turn_count == -1 {
-                -offset
-            } else {
-                offset
+        if let Some((target_transform, circuit, _)) = target {
+            // look ahead along the track
+            let offset = match circuit.heading() {
+                Some(heading) if !circuit.in_turn => heading.to_vec2().extend(0.) * 50.,
+                _ => Vec3::ZERO,
             };
 
             camera_transform.translation = camera_transform.translation.lerp(
@@ -67,19 +60,23 @@ fn follow_target(
 
 fn resizing(
     window_query: Query<&Window, With<PrimaryWindow>>,
-    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut camera_query: Query<
+        &mut OrthographicProjection,
+        Or<(With<MainCamera>, With<PlayerCamera>)>,
+    >,
 ) {
     if !window_query.is_empty() && !camera_query.is_empty() {
         let window = window_query.single();
-        let mut projection = camera_query.single_mut();
 
         let start_scale = 0.4;
         let start_width = 1280.;
 
-        if start_width == window.width().ceil() {
-            projection.scale = start_scale;
-        } else {
-            projection.scale = start_scale * (start_width / window.width());
+        for mut projection in camera_query.iter_mut() {
+            if start_width == window.width().ceil() {
+                projection.scale = start_scale;
+            } else {
+                projection.scale = start_scale * (start_width / window.width());
+            }
         }
     }
 }
@@ -1,22 +1,20 @@
-use std::default;
-
-use bevy::{color::palettes::css::BLUE, prelude::*};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{screen::Screen, AppSet};
 
 use super::{
-    collider::{collide, Collider, Collision},
-    map::chunk::ChunkConnextion,
-    movements::Velocity,
-    spawn::{
-        map::{ChunkConnextions, ChunkRoad, ChunkTag, EndChunk},
-        player::Player,
-    },
+    letter::Letters,
+    map::{chunk::ChunkConnextion, road::RoadNetwork},
+    spawn::player::Player,
     GameState,
 };
 
+/// How many chunks the player can cut through at once
+const MAX_SKIPPED_CHUNKS: usize = 2;
+
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<Circuit>();
+    app.register_type::<(Circuit, CircuitDuration)>();
     app.add_systems(
         Update,
         (
@@ -37,36 +35,209 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-#[derive(Resource, Reflect, Debug, Default)]
-#[reflect(Resource)]
+/// Progress of a player along the track
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
 pub struct Circuit {
-    pub current_orientation: CircuitOrientation,
-    pub turn_count: i32,
-    #[reflect(ignore)]
-    pub turn: Vec<Entity>,
-    #[reflect(ignore)]
-    pub already_collide: Vec<Entity>,
+    /// Road chunks of the loop in clockwise order, starting from the chunk at the start position
+    pub track: Vec<IVec2>,
+    /// Indices of the checkpoints, counted in the chosen direction
+    pub checkpoints: Vec<usize>,
+    /// Number of road chunks ridden along the track
+    pub progress: usize,
+    /// Index in `checkpoints` of the next checkpoint to reach in this lap
+    pub next_checkpoint: usize,
+    /// Number of laps to ride
+    pub laps: usize,
+    /// Current lap, starts at 0
+    pub lap: usize,
     pub in_turn: bool,
     pub direction: CircuitDirection,
     pub direction_chosen: bool,
 }
 
-#[derive(Resource, Reflect, Debug, Default)]
-#[reflect(Resource)]
-pub struct CircuitDuration(pub f32);
+impl Circuit {
+    pub fn new(track: Vec<IVec2>, checkpoints: Vec<usize>, laps: usize) -> Self {
+        Self {
+            track,
+            checkpoints,
+            laps: laps.max(1),
+            ..default()
+        }
+    }
+
+    pub fn choose_direction(&mut self, direction: CircuitDirection) {
+        if direction == CircuitDirection::AntiClockwise {
+            let len = self.track.len();
+            self.checkpoints = self.checkpoints.iter().rev().map(|c| len - c).collect();
+        }
+
+        self.direction = direction;
+        self.direction_chosen = true;
+        self.in_turn = self.heading_in() != self.heading();
+    }
 
-#[derive(Debug, Reflect, Default, PartialEq, Eq)]
+    /// Where the road goes from the end chunk when riding in `direction`
+    pub fn start_heading(&self, direction: CircuitDirection) -> Option<ChunkConnextion> {
+        let start = *self.track.first()?;
+        let next = match direction {
+            CircuitDirection::Clockwise => *self.track.get(1)?,
+            CircuitDirection::AntiClockwise => *self.track.last()?,
+        };
+        ChunkConnextion::from_grid_offset(next - start)
+    }
+
+    /// Road chunk at `index` along the track in the chosen direction
+    pub fn cell_at(&self, index: usize) -> Option<IVec2> {
+        let len = self.track.len();
+        if len == 0 {
+            return None;
+        }
+
+        let index = index % len;
+        Some(match self.direction {
+            CircuitDirection::Clockwise => self.track[index],
+            CircuitDirection::AntiClockwise => self.track[(len - index) % len],
+        })
+    }
+
+    pub fn current_cell(&self) -> Option<IVec2> {
+        self.cell_at(self.progress)
+    }
+
+    pub fn previous_cell(&self) -> Option<IVec2> {
+        self.cell_at(self.progress + self.track.len().max(1) - 1)
+    }
+
+    /// The cell is the current chunk, the one before or one the player can reach next
+    pub fn is_on_course(&self, cell: IVec2) -> bool {
+        self.previous_cell() == Some(cell)
+            || (0..=MAX_SKIPPED_CHUNKS).any(|step| self.cell_at(self.progress + step) == Some(cell))
+    }
+
+    /// Track index of the last checkpoint reached, the start of a lap counts as one
+    pub fn last_checkpoint(&self) -> usize {
+        let lap_start = self.lap * self.track.len();
+        match self.next_checkpoint.checked_sub(1) {
+            Some(checkpoint) => lap_start + self.checkpoints[checkpoint],
+            None => lap_start,
+        }
+    }
+
+    /// Go back to the last checkpoint, returns its chunk and where the road goes from it
+    pub fn rewind_to_checkpoint(&mut self) -> Option<(IVec2, ChunkConnextion)> {
+        self.progress = self.last_checkpoint();
+        self.in_turn = self.heading_in() != self.heading();
+
+        Some((self.current_cell()?, self.heading()?))
+    }
+
+    /// Where the road goes from the current chunk
+    pub fn heading(&self) -> Option<ChunkConnextion> {
+        self.heading_at(self.progress)
+    }
+
+    fn heading_in(&self) -> Option<ChunkConnextion> {
+        self.heading_at(self.progress + self.track.len().max(1) - 1)
+    }
+
+    fn heading_at(&self, index: usize) -> Option<ChunkConnextion> {
+        if !self.direction_chosen {
+            return None;
+        }
+
+        ChunkConnextion::from_grid_offset(self.cell_at(index + 1)? - self.cell_at(index)?)
+    }
+
+    /// Rotation the player should have on the current chunk
+    pub fn expected_angle(&self) -> f32 {
+        self.heading().map(|heading| heading.angle()).unwrap_or(0.)
+    }
+
+    /// Move forward when the player reaches one of the next chunks of the track,
+    /// returns `true` when a lap is completed
+    pub fn advance(&mut self, cell: IVec2) -> bool {
+        if !self.direction_chosen || self.finished() {
+            return false;
+        }
+
+        for step in 1..=MAX_SKIPPED_CHUNKS {
+            if self.cell_at(self.progress + step) == Some(cell) {
+                self.progress += step;
+
+                let lap_start = self.lap * self.track.len();
+                while self
+                    .checkpoints
+                    .get(self.next_checkpoint)
+                    .is_some_and(|checkpoint| lap_start + *checkpoint <= self.progress)
+                {
+                    self.next_checkpoint += 1;
+                }
+
+                self.in_turn = self.heading_in() != self.heading();
+
+                // the player rode all the loop and passed all checkpoints
+                if self.progress >= lap_start + self.track.len()
+                    && self.next_checkpoint >= self.checkpoints.len()
+                {
+                    self.lap += 1;
+                    self.next_checkpoint = 0;
+                    return true;
+                }
+                break;
+            }
+        }
+
+        false
+    }
+
+    /// All laps are ridden
+    pub fn finished(&self) -> bool {
+        !self.track.is_empty() && self.lap >= self.laps
+    }
+}
+
+/// Timing of the run of a player, lap by lap
+#[derive(Component, Reflect, Debug, Default, Clone)]
+#[reflect(Component)]
+pub struct CircuitDuration {
+    pub total: f32,
+    /// Time of the lap being ridden
+    pub current: f32,
+    /// Times of the finished laps
+    pub laps: Vec<f32>,
+}
+
+impl CircuitDuration {
+    pub fn tick(&mut self, delta: f32) {
+        self.total += delta;
+        self.current += delta;
+    }
+
+    pub fn complete_lap(&mut self) {
+        self.laps.push(self.current);
+        self.current = 0.;
+    }
+
+    pub fn best_lap(&self) -> Option<f32> {
+        self.laps.iter().copied().reduce(f32::min)
+    }
+}
+
+#[derive(Debug, Reflect, Serialize, Deserialize, Default, PartialEq, Eq, Clone, Copy)]
 pub enum CircuitDirection {
     #[default]
     Clockwise,
     AntiClockwise,
 }
 
-#[derive(Debug, Reflect, Default, PartialEq, Eq, Clone)]
-pub enum CircuitOrientation {
-    Horizontal,
-    #[default]
-    Vertical,
+impl CircuitDirection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CircuitDirection::Clockwise => "clockwise",
+            CircuitDirection::AntiClockwise => "anticlockwise",
+        }
+    }
 }
 
 #[derive(Resource, Reflect, Debug, Deref, DerefMut)]
@@ -80,143 +251,38 @@ impl Default for EndCircuitTimer {
 }
 
 fn update_circuit(
-    // mut gizmos: Gizmos,
-    time: Res<Time>,
-    mut circuit: ResMut<Circuit>,
-    player_query: Query<&Collider, With<Player>>,
-    chunks_query: Query<
-        (Entity, &Collider, &ChunkRoad, &ChunkConnextions),
-        (With<ChunkTag>, Without<Player>),
-    >,
+    mut player_query: Query<(&Transform, &mut Circuit, &mut CircuitDuration), With<Player>>,
 ) {
-    if let Ok(player_collider) = player_query.get_single() {
-        for (chunk_entity, chunk_collider, orientation, connextions) in chunks_query.iter() {
-            if let Some(collision) = collide(
-                player_collider.center().extend(0.),
-                player_collider.size(),
-                chunk_collider.center().extend(0.),
-                chunk_collider.size(),
-            ) {
-                match &orientation {
-                    ChunkRoad::Turn => {
-                        // #[cfg(feature = "dev")]
-                        // gizmos.rect_2d(
-                        //     chunk_collider.center(),
-                        //     0.,
-                        //     chunk_collider.size() - 10.,
-                        //     Color::Srgba(BLUE),
-                        // );
-
-                        if !circuit.turn.contains(&chunk_entity) {
-                            let t = match (&collision, &circuit.current_orientation) {
-                                (Collision::Bottom, CircuitOrientation::Vertical) => {
-                                    println!("1");
-                                    let r = if connextions.contains(&ChunkConnextion::Left) {
-                                        println!("Turn left");
-                                        -1
-                                    } else if connextions.contains(&ChunkConnextion::Right) {
-                                        println!("Turn right");
-                                        1
-                                    } else {
-                                        0
-                                    };
-                                    r
-                                }
-                                (Collision::Left, CircuitOrientation::Horizontal) => {
-                                    println!("2");
-                                    let r = if connextions.contains(&ChunkConnextion::Top) {
-                                        println!("Turn left");
-                                        -1
-                                    } else if connextions.contains(&ChunkConnextion::Bottom) {
-                                        println!("Turn right");
-                                        1
-                                    } else {
-                                        0
-                                    };
-                                    r
-                                }
-                                (Collision::Top, CircuitOrientation::Vertical) => {
-                                    println!("3");
-                                    let r = if connextions.contains(&ChunkConnextion::Left) {
-                                        println!("Turn right");
-                                        1
-                                    } else if connextions.contains(&ChunkConnextion::Right) {
-                                        println!("Turn left");
-                                        -1
-                                    } else {
-                                        0
-                                    };
-                                    r
-                                }
-                                (Collision::Right, CircuitOrientation::Horizontal) => {
-                                    println!("4");
-                                    let r = if connextions.contains(&ChunkConnextion::Top) {
-                                        println!("Turn right");
-                                        1
-                                    } else if connextions.contains(&ChunkConnextion::Bottom) {
-                                        println!("Turn left");
-                                        -1
-                                    } else {
-                                        0
-                                    };
-                                    r
-                                }
-
-                                _ => 0,
-                            };
-
-                            circuit.in_turn = true;
-
-                            circuit.turn_count += t;
-
-                            circuit.current_orientation = match circuit.current_orientation {
-                                CircuitOrientation::Horizontal => CircuitOrientation::Vertical,
-                                CircuitOrientation::Vertical => CircuitOrientation::Horizontal,
-                            };
-
-                            circuit.turn.push(chunk_entity);
-                        }
-                    }
-                    _ => {
-                        if !circuit.already_collide.contains(&chunk_entity) {
-                            circuit.in_turn = false;
-                            circuit.already_collide.push(chunk_entity);
-                        }
-                    }
-                }
-            }
+    for (transform, mut circuit, mut circuit_duration) in player_query.iter_mut() {
+        if circuit.advance(RoadNetwork::cell_at(transform.translation.xy())) {
+            circuit_duration.complete_lap();
         }
     }
 }
 
 fn update_circuit_duration(
     time: Res<Time>,
-    circuit: Res<Circuit>,
-    mut circuit_duration: ResMut<CircuitDuration>,
+    mut player_query: Query<(&Circuit, &mut CircuitDuration), With<Player>>,
 ) {
-    if circuit.direction_chosen {
-        circuit_duration.0 += time.delta_seconds();
+    for (circuit, mut circuit_duration) in player_query.iter_mut() {
+        if circuit.direction_chosen {
+            circuit_duration.tick(time.delta_seconds());
+        }
     }
 }
 
-/// When postman collide the end chunk
+/// When a postman rode all the laps and brought the collected mail back
 fn check_end(
-    mut player_query: Query<&Collider, With<Player>>,
-    chunk_query: Query<&Collider, (With<EndChunk>, Without<Player>)>,
-    circuit: Res<Circuit>,
+    player_query: Query<(&Circuit, &Letters), With<Player>>,
     mut next_state: ResMut<NextState<GameState>>,
     current_state: Res<State<GameState>>,
 ) {
-    if circuit.already_collide.len() > 3 && *current_state.get() != GameState::End {
-        if let Ok(player_collider) = player_query.get_single_mut() {
-            if let Ok(chunk_collider) = chunk_query.get_single() {
-                if player_collider.collide(chunk_collider) {
-                    // is end of circuit !
-                    println!("End");
-                    next_state.set(GameState::End);
-                }
-            }
-        }
+    let finished = player_query
+        .iter()
+        .any(|(circuit, letters)| circuit.finished() && letters.mail_carried == 0);
+
+    if finished && *current_state.get() != GameState::End {
+        next_state.set(GameState::End);
     }
 }
 
@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+
+use crate::{screen::Screen, AppSet};
+
+use super::{
+    circuit::Circuit,
+    map::road::RoadNetwork,
+    movements::Velocity,
+    rival::Rival,
+    spawn::player::{Player, PlayerController, PlayerMovement},
+    ui::InfoText,
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CourseWatch>();
+    app.observe(respawn_at_checkpoint);
+    app.add_systems(
+        Update,
+        watch_course
+            .in_set(AppSet::Update)
+            .run_if(in_state(GameState::Run).and_then(in_state(Screen::Playing))),
+    );
+}
+
+/// Speed against the track direction from which the player rides the wrong way
+const WRONG_WAY_SPEED: f32 = 0.3;
+/// Time before warning the player
+const WARNING_DELAY: f32 = 0.5;
+/// Time before the player is put back on the track
+const RESPAWN_DELAY: f32 = 3.;
+
+// NOTE - to trigger this event : `commands.trigger(RespawnAtCheckpoint(player));`
+#[derive(Event, Debug)]
+pub struct RespawnAtCheckpoint(pub Entity);
+
+/// How long a player has been riding backwards or away from the track
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct CourseWatch {
+    pub wrong_way: f32,
+    pub off_course: f32,
+    warning: bool,
+}
+
+fn watch_course(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Velocity,
+            &PlayerController,
+            &Circuit,
+            &mut CourseWatch,
+            Has<Rival>,
+        ),
+        With<Player>,
+    >,
+    mut info_text: ResMut<InfoText>,
+) {
+    for (entity, transform, velocity, controller, circuit, mut watch, rival) in
+        player_query.iter_mut()
+    {
+        if !circuit.direction_chosen || controller.damn {
+            continue;
+        }
+
+        let cell = RoadNetwork::cell_at(transform.translation.xy());
+        let backwards = circuit.previous_cell() == Some(cell)
+            || (!circuit.in_turn
+                && circuit
+                    .heading()
+                    .is_some_and(|heading| velocity.dot(heading.to_vec2()) < -WRONG_WAY_SPEED));
+
+        let delta = time.delta_seconds();
+        watch.wrong_way = if backwards {
+            watch.wrong_way + delta
+        } else {
+            0.
+        };
+        watch.off_course = if circuit.is_on_course(cell) {
+            0.
+        } else {
+            watch.off_course + delta
+        };
+
+        let elapsed = watch.wrong_way.max(watch.off_course);
+        if elapsed >= RESPAWN_DELAY {
+            commands.trigger(RespawnAtCheckpoint(entity));
+        } else if rival {
+            // nobody to warn
+        } else if elapsed >= WARNING_DELAY {
+            let warning = if watch.wrong_way >= watch.off_course {
+                "Wrong way!"
+            } else {
+                "Off course!"
+            };
+            info_text.set(format!(
+                "{} Back to the checkpoint in {:.0}",
+                warning,
+                (RESPAWN_DELAY - elapsed).ceil()
+            ));
+            watch.warning = true;
+        } else if watch.warning {
+            info_text.reset();
+            watch.warning = false;
+        }
+    }
+}
+
+/// Put the player back on their last checkpoint, facing the right way
+fn respawn_at_checkpoint(
+    trigger: Trigger<RespawnAtCheckpoint>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut PlayerMovement,
+            &mut PlayerController,
+            &mut Circuit,
+            &mut CourseWatch,
+            Has<Rival>,
+        ),
+        With<Player>,
+    >,
+    mut info_text: ResMut<InfoText>,
+) {
+    let Ok((
+        mut transform,
+        mut velocity,
+        mut movement,
+        mut controller,
+        mut circuit,
+        mut watch,
+        rival,
+    )) = player_query.get_mut(trigger.event().0)
+    else {
+        return;
+    };
+    let Some((cell, heading)) = circuit.rewind_to_checkpoint() else {
+        return;
+    };
+
+    let position = RoadNetwork::center(cell);
+    transform.translation = position.extend(transform.translation.z);
+    transform.rotation = Quat::from_rotation_z(heading.angle());
+    velocity.0 = Vec2::ZERO;
+    movement.drifting = false;
+    movement.slip = 0.;
+    controller.actual_chunk = None;
+    controller.actual_collision = None;
+    controller.last_obstacle = None;
+
+    *watch = CourseWatch::default();
+    if !rival {
+        info_text.reset();
+    }
+}
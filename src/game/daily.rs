@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+use crate::screen::{playing::CurrentLevel, Screen};
+
+use super::{
+    circuit::{Circuit, CircuitDuration},
+    letter::Letters,
+    replay::ReplayPlayback,
+    save::{day_string, local_day, now, GameSave, RecordResult, RunRecord},
+    score::ScoreModel,
+    spawn::player::{Health, Player},
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DailyRoute>();
+    app.add_systems(OnEnter(Screen::Title), leave_daily_route);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        count_daily_attempt
+            .run_if(resource_exists::<DailyRoute>.and_then(not(resource_exists::<ReplayPlayback>))),
+    );
+    app.add_systems(
+        OnEnter(GameState::End),
+        save_daily_run
+            .run_if(resource_exists::<DailyRoute>.and_then(not(resource_exists::<ReplayPlayback>))),
+    );
+}
+
+/// Mixes the day into a seed far from the seeds of the days around it
+const SEED_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+const SEED_SALT: u64 = 0x5057_4D41_4E00_0000;
+
+/// Ride the route of the day, the same for everyone playing that day, kept across restarts
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[reflect(Resource)]
+pub struct DailyRoute {
+    /// Days since the Unix epoch, of the local date
+    pub day: u64,
+    pub seed: u64,
+}
+
+impl DailyRoute {
+    pub fn today() -> Self {
+        Self::of_day(local_day(now()))
+    }
+
+    pub fn of_day(day: u64) -> Self {
+        Self {
+            day,
+            seed: day.wrapping_mul(SEED_MULTIPLIER) ^ SEED_SALT,
+        }
+    }
+
+    /// Level whose layout the route is built on
+    pub fn level(&self, levels: usize) -> i32 {
+        (self.day % levels.max(1) as u64) as i32
+    }
+
+    /// Day of the route as `year-month-day`
+    pub fn date_string(&self) -> String {
+        day_string(self.day)
+    }
+}
+
+fn leave_daily_route(mut commands: Commands) {
+    commands.remove_resource::<DailyRoute>();
+}
+
+fn count_daily_attempt(
+    mut game_save: ResMut<GameSave>,
+    route: Res<DailyRoute>,
+    current_level: Res<CurrentLevel>,
+) {
+    let record = game_save.daily_record_mut(route.day);
+    record.level = current_level.indice;
+    record.attempts += 1;
+}
+
+fn save_daily_run(
+    mut commands: Commands,
+    mut game_save: ResMut<GameSave>,
+    route: Res<DailyRoute>,
+    score_model: Res<ScoreModel>,
+    player_query: Query<(&Circuit, &CircuitDuration, &Letters, &Health), With<Player>>,
+) {
+    let Ok((circuit, circuit_duration, letters, health)) = player_query.get_single() else {
+        return;
+    };
+    let score = score_model.score(letters, circuit_duration.total, health.hits);
+    let run = RunRecord {
+        time: circuit_duration.total,
+        score: score.points,
+        letters: letters.delivered(),
+        stars: score.stars,
+        direction: circuit.direction,
+        date: now(),
+    };
+
+    let record = game_save.daily_record_mut(route.day);
+    let previous_best = record.best.clone();
+    let new_record = previous_best
+        .as_ref()
+        .is_none_or(|best| run.time < best.time);
+    if new_record {
+        record.best = Some(run);
+    }
+
+    commands.insert_resource(RecordResult {
+        new_record,
+        previous_best,
+        previous_best_lap: None,
+    });
+
+    game_save.save();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_of_day_same_route() {
+        let route = DailyRoute::of_day(20000);
+        let again = DailyRoute::of_day(20000);
+
+        assert_eq!(route.day, 20000);
+        assert_eq!(route.seed, again.seed);
+    }
+
+    #[test]
+    fn test_of_day_changes_every_day() {
+        let seeds: Vec<u64> = (20000..20030)
+            .map(|day| DailyRoute::of_day(day).seed)
+            .collect();
+
+        for (i, seed) in seeds.iter().enumerate() {
+            assert!(!seeds[i + 1..].contains(seed));
+        }
+    }
+
+    #[test]
+    fn test_level_in_range() {
+        for day in 20000..20030 {
+            assert!((0..5).contains(&DailyRoute::of_day(day).level(5)));
+        }
+        // no level to build on
+        assert_eq!(DailyRoute::of_day(20000).level(0), 0);
+    }
+
+    #[test]
+    fn test_date_string() {
+        assert_eq!(DailyRoute::of_day(19723).date_string(), "2024-01-01");
+    }
+}
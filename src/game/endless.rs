@@ -0,0 +1,619 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::{color::palettes::css::WHITE, prelude::*, ui::Val::*, utils::HashSet};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    screen::Screen,
+    ui::{palette::BACKGROUND, prelude::*},
+};
+
+use super::{
+    assets::{
+        handles::{AsepriteAssets, FontAssets, LdtkAssets},
+        loaders::ldtk::LdtkAsset,
+    },
+    audio::soundtrack::PlaySoundtrack,
+    circuit::{Circuit, CircuitDirection},
+    letter::{Address, Delivery, DeliveryKind, Letters},
+    map::{
+        builder::{MapBuilder, Obstacle, CHECKPOINT_INTERVAL},
+        chunk::{Chunk, ChunkConnextion, ChunkType, RoadChunkType, PIXEL_CHUNK_SIZE},
+        road::RoadNetwork,
+        types::ObstacleType,
+    },
+    rng::GameRng,
+    save::{now, EndlessRecord, GameSave},
+    spawn::{
+        map::{spawn_chunk, spawn_decor_chunk, spawn_obstacle, ChunkAssets, HouseSetup, MapTag},
+        player::{Health, Player, SpawnPlayer},
+    },
+    ui::{EndAction, InfoText},
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(EndlessMode, EndlessText)>();
+    app.observe(spawn_endless);
+    app.add_systems(OnEnter(Screen::Title), leave_endless_mode);
+    app.add_systems(
+        Update,
+        (
+            stream_chunks,
+            (tick_time_budget, end_on_crash).run_if(in_state(GameState::Run)),
+            update_endless_text,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing).and_then(resource_exists::<EndlessRun>)),
+    );
+    app.add_systems(
+        OnEnter(GameState::End),
+        save_endless_run.run_if(resource_exists::<EndlessRun>),
+    );
+    app.add_systems(
+        OnEnter(GameState::EndScreen),
+        spawn_endless_end_ui.run_if(resource_exists::<EndlessRun>),
+    );
+}
+
+/// Road chunks laid ahead of the player
+const CHUNKS_AHEAD: usize = 8;
+/// Road chunks kept behind the player
+const CHUNKS_BEHIND: usize = 4;
+/// Straight chunks between two detours
+const MIN_STRAIGHT: usize = 2;
+/// Longest detour, in chunks
+const MAX_DETOUR: usize = 2;
+/// Distance, in road chunks, at which the road is the hardest
+const HARDEST_DISTANCE: f32 = 100.;
+const HOUSE_CHANCE: f64 = 0.5;
+const DECOR_CHANCE: f64 = 0.3;
+/// Seconds on the clock at the start
+const START_TIME: f32 = 30.;
+/// Seconds given for a delivery, at the start and far along the road
+const DELIVERY_TIME: (f32, f32) = (8., 3.);
+const DISTANCE_POINTS: i32 = 10;
+const LETTER_POINTS: i32 = 100;
+const PARCEL_POINTS: i32 = 50;
+/// The road starts next to the post office
+const START: IVec2 = IVec2::ZERO;
+
+/// Ride the endless road instead of a level, kept across restarts
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct EndlessMode;
+
+#[derive(Event, Debug)]
+pub struct SpawnEndless;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct EndlessText;
+
+/// The road laid ahead of the player in the endless mode
+#[derive(Resource)]
+pub struct EndlessRun {
+    templates: HashMap<ChunkType, Chunk>,
+    layout: Handle<TextureAtlasLayout>,
+    map: Entity,
+    /// Last road chunk laid
+    cursor: IVec2,
+    /// Where the road goes from the last chunk
+    heading: ChunkConnextion,
+    /// Straight chunks left before the road can turn
+    straight: usize,
+    /// Chunks left before a detour goes back to the right
+    detour: usize,
+    /// Cells taken by the road, houses and decor
+    occupied: HashSet<IVec2>,
+    /// Spawned chunks, with the index in the track of the road they go with
+    spawned: VecDeque<(usize, Entity)>,
+    next_address: u32,
+    /// Seconds left before the run ends
+    pub time_left: f32,
+    /// Deliveries already paid in time
+    delivered: i32,
+    /// Best run before this one
+    pub previous_best: Option<EndlessRecord>,
+}
+
+impl EndlessRun {
+    /// A chunk of the templates at a free cell
+    fn place(
+        &mut self,
+        chunk_type: ChunkType,
+        cell: IVec2,
+        connextions: Vec<ChunkConnextion>,
+    ) -> Option<Chunk> {
+        if self.occupied.contains(&cell) {
+            return None;
+        }
+
+        let mut chunk = self.templates.get(&chunk_type)?.clone();
+        chunk.position = Vec2::new(
+            PIXEL_CHUNK_SIZE * cell.x as f32,
+            -PIXEL_CHUNK_SIZE * cell.y as f32,
+        );
+        chunk.connextions = connextions;
+        self.occupied.insert(cell);
+
+        Some(chunk)
+    }
+
+    /// The road goes right, with detours up or down
+    fn next_road(&mut self, rng: &mut impl Rng, distance: usize) -> (IVec2, Chunk) {
+        let cell = self.cursor + self.heading.grid_offset();
+        let incoming = self.heading.opposite();
+
+        let outgoing = if self.heading != ChunkConnextion::Right {
+            if self.detour > 0 {
+                self.detour -= 1;
+                self.heading
+            } else {
+                self.straight = MIN_STRAIGHT;
+                ChunkConnextion::Right
+            }
+        } else if self.straight == 0 && rng.gen_bool(turn_chance(distance)) {
+            let side = if rng.gen_bool(0.5) {
+                ChunkConnextion::Top
+            } else {
+                ChunkConnextion::Bottom
+            };
+            let detour = rng.gen_range(1..=MAX_DETOUR);
+
+            // the detour and the way back need free cells
+            let end = cell + side.grid_offset() * (detour as i32 + 1);
+            let free = (1..=detour as i32 + 1)
+                .map(|step| cell + side.grid_offset() * step)
+                .chain([end + ChunkConnextion::Right.grid_offset()])
+                .all(|cell| !self.occupied.contains(&cell));
+
+            if free {
+                self.detour = detour;
+                side
+            } else {
+                ChunkConnextion::Right
+            }
+        } else {
+            self.straight = self.straight.saturating_sub(1);
+            ChunkConnextion::Right
+        };
+
+        let (chunk_type, flip_x, flip_y) = if outgoing == self.heading {
+            match outgoing {
+                ChunkConnextion::Left | ChunkConnextion::Right => {
+                    (RoadChunkType::Horizontal, false, false)
+                }
+                _ => (RoadChunkType::Vertical, false, false),
+            }
+        } else {
+            let (flip_x, flip_y) = turn_flips(incoming, outgoing);
+            (RoadChunkType::Turn, flip_x, flip_y)
+        };
+
+        let mut chunk = self
+            .place(ChunkType::Road(chunk_type), cell, vec![incoming, outgoing])
+            .expect("the road is laid on free cells");
+        chunk.flip_x = flip_x;
+        chunk.flip_y = flip_y;
+
+        self.cursor = cell;
+        self.heading = outgoing;
+
+        (cell, chunk)
+    }
+}
+
+/// Flips of the `Turn` chunk linking two sides, the template links the right and the bottom
+fn turn_flips(a: ChunkConnextion, b: ChunkConnextion) -> (bool, bool) {
+    let links = |c: ChunkConnextion, d: ChunkConnextion| (a == c && b == d) || (a == d && b == c);
+
+    if links(ChunkConnextion::Right, ChunkConnextion::Bottom) {
+        (false, false)
+    } else if links(ChunkConnextion::Bottom, ChunkConnextion::Left) {
+        (true, false)
+    } else if links(ChunkConnextion::Top, ChunkConnextion::Right) {
+        (false, true)
+    } else {
+        (true, true)
+    }
+}
+
+/// From 0 at the start to 1 far along the road
+fn difficulty(distance: usize) -> f32 {
+    (distance as f32 / HARDEST_DISTANCE).min(1.)
+}
+
+fn turn_chance(distance: usize) -> f64 {
+    (0.1 + 0.25 * difficulty(distance)) as f64
+}
+
+fn obstacle_chance(distance: usize) -> f64 {
+    (0.1 + 0.35 * difficulty(distance)) as f64
+}
+
+fn parcel_ratio(distance: usize) -> f32 {
+    0.3 * difficulty(distance)
+}
+
+fn delivery_time(distance: usize) -> f32 {
+    DELIVERY_TIME.0 + (DELIVERY_TIME.1 - DELIVERY_TIME.0) * difficulty(distance)
+}
+
+pub fn endless_score(distance: usize, letters: &Letters) -> i32 {
+    distance as i32 * DISTANCE_POINTS
+        + letters.delivered() * LETTER_POINTS
+        + letters.parcels * PARCEL_POINTS
+}
+
+fn spawn_endless(
+    _trigger: Trigger<SpawnEndless>,
+    mut commands: Commands,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    ldtks: Res<LdtkAssets>,
+    assets: ChunkAssets,
+    fonts: Res<FontAssets>,
+    game_save: Res<GameSave>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 25, 25, None, None);
+    let builder = MapBuilder::new(
+        ldtk_assets.get(&ldtks.get("maps")).unwrap().project.clone(),
+        ldtk_assets
+            .get(&ldtks.get("chunks"))
+            .unwrap()
+            .project
+            .clone(),
+    );
+
+    let map = commands
+        .spawn((
+            Name::new("Map"),
+            StateScoped(Screen::Playing),
+            SpatialBundle::default(),
+            MapTag,
+        ))
+        .id();
+
+    let mut run = EndlessRun {
+        templates: builder.templates(),
+        layout: texture_atlases.add(layout),
+        map,
+        cursor: START + ChunkConnextion::Left.grid_offset(),
+        heading: ChunkConnextion::Right,
+        straight: MIN_STRAIGHT,
+        detour: 0,
+        occupied: HashSet::default(),
+        spawned: VecDeque::new(),
+        next_address: 1,
+        time_left: START_TIME,
+        delivered: 0,
+        previous_best: game_save.endless_best.clone(),
+    };
+
+    // the post office the road starts from
+    if let Some(post_office) = run.place(
+        ChunkType::PostOffice,
+        run.cursor,
+        vec![ChunkConnextion::Right],
+    ) {
+        let layout = run.layout.clone();
+        let spawned = spawn_chunk(
+            &mut commands,
+            &assets,
+            &layout,
+            &post_office,
+            HouseSetup::default(),
+            &mut game_rng.rng,
+        );
+        commands.entity(map).add_child(spawned.entity);
+        run.spawned.push_back((0, spawned.entity));
+    }
+
+    commands.insert_resource(run);
+
+    commands.trigger(SpawnPlayer::new(
+        RoadNetwork::center(START) - Vec2::X * PIXEL_CHUNK_SIZE / 4.,
+        Circuit::new(Vec::new(), Vec::new(), 1),
+        Letters::init(0),
+    ));
+
+    commands.spawn((
+        Name::new("Endless Text"),
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.,
+                color: Color::Srgba(WHITE),
+                font: fonts.get("gamer"),
+            },
+        )
+        .with_style(Style {
+            top: Px(130.),
+            left: Px(20.),
+            position_type: PositionType::Absolute,
+            ..default()
+        }),
+        StateScoped(Screen::Playing),
+        EndlessText,
+    ));
+}
+
+fn stream_chunks(
+    mut commands: Commands,
+    mut run: ResMut<EndlessRun>,
+    mut player_query: Query<(&mut Circuit, &mut Letters), With<Player>>,
+    mut game_rng: ResMut<GameRng>,
+    assets: ChunkAssets,
+    children_query: Query<&Children>,
+    delivery_query: Query<(&Delivery, &Address)>,
+) {
+    let Ok((mut circuit, mut letters)) = player_query.get_single_mut() else {
+        return;
+    };
+    let rng = &mut game_rng.rng;
+    let distance = circuit.progress;
+    let layout = run.layout.clone();
+
+    // lay the road ahead
+    while circuit.track.len() < circuit.progress + CHUNKS_AHEAD {
+        let index = circuit.track.len();
+        let (cell, road) = run.next_road(rng, distance);
+
+        circuit.track.push(cell);
+        if index > 0 && index % CHECKPOINT_INTERVAL == 0 {
+            circuit.checkpoints.push(index);
+        }
+
+        let mut entities = vec![
+            spawn_chunk(
+                &mut commands,
+                &assets,
+                &layout,
+                &road,
+                HouseSetup::default(),
+                rng,
+            )
+            .entity,
+        ];
+
+        let horizontal = road.chunk_type == ChunkType::Road(RoadChunkType::Horizontal);
+        let vertical = road.chunk_type == ChunkType::Road(RoadChunkType::Vertical);
+
+        // the start is kept clear
+        if (horizontal || vertical) && index > 1 {
+            if rng.gen_bool(obstacle_chance(distance)) {
+                let obstacle = Obstacle {
+                    chunk: road.clone(),
+                    chunk_center: RoadNetwork::center(cell),
+                    obstacle_type: *[
+                        ObstacleType::WatterPuddle,
+                        ObstacleType::RoadWork,
+                        ObstacleType::ManholeCover,
+                        ObstacleType::WorkCone,
+                    ]
+                    .choose(rng)
+                    .unwrap(),
+                };
+
+                let obstacles = commands
+                    .spawn((Name::new("Obstacles"), SpatialBundle::default()))
+                    .with_children(|children| spawn_obstacle(children, &assets, &obstacle, rng))
+                    .id();
+                entities.push(obstacles);
+            }
+
+            // a house on one side of the road, trees on the other
+            let mut sides = if horizontal {
+                [ChunkConnextion::Top, ChunkConnextion::Bottom]
+            } else {
+                [ChunkConnextion::Left, ChunkConnextion::Right]
+            };
+            if rng.gen_bool(0.5) {
+                sides.swap(0, 1);
+            }
+
+            if rng.gen_bool(HOUSE_CHANCE) {
+                let house = run.place(
+                    ChunkType::House,
+                    cell + sides[0].grid_offset(),
+                    vec![sides[0].opposite()],
+                );
+
+                if let Some(house) = house {
+                    let address = run.next_address;
+                    run.next_address += 1;
+
+                    let spawned = spawn_chunk(
+                        &mut commands,
+                        &assets,
+                        &layout,
+                        &house,
+                        HouseSetup {
+                            address,
+                            parcel_ratio: parcel_ratio(distance),
+                            mail_ratio: 0.,
+                        },
+                        rng,
+                    );
+
+                    letters.all += 1;
+                    letters.to_post += 1;
+                    if spawned.delivery == Some(DeliveryKind::Letter) {
+                        letters.queue.push(address);
+                    }
+                    entities.push(spawned.entity);
+                }
+            }
+
+            if rng.gen_bool(DECOR_CHANCE) {
+                let decor = ChunkType::Decor(rng.gen_range(1..=4));
+                if let Some(decor) = run.place(decor, cell + sides[1].grid_offset(), Vec::new()) {
+                    entities.push(spawn_decor_chunk(&mut commands, &assets, &decor, rng));
+                }
+            }
+        }
+
+        commands.entity(run.map).push_children(&entities);
+        run.spawned
+            .extend(entities.into_iter().map(|entity| (index, entity)));
+    }
+
+    if !circuit.direction_chosen && circuit.track.len() > 1 {
+        circuit.choose_direction(CircuitDirection::Clockwise);
+    }
+
+    // clear the road behind
+    while let Some((index, entity)) = run.spawned.front().copied() {
+        if index + CHUNKS_BEHIND >= circuit.progress {
+            break;
+        }
+        run.spawned.pop_front();
+
+        // houses left behind without their letter are missed
+        for child in children_query.iter_descendants(entity) {
+            if let Ok((delivery, address)) = delivery_query.get(child) {
+                if *delivery != Delivery::Delivered {
+                    letters.miss(address.0);
+                }
+            }
+        }
+
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn tick_time_budget(
+    time: Res<Time>,
+    player_query: Query<(&Circuit, &Letters), With<Player>>,
+    mut run: ResMut<EndlessRun>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut info_text: ResMut<InfoText>,
+) {
+    let Ok((circuit, letters)) = player_query.get_single() else {
+        return;
+    };
+
+    // deliveries buy time, less and less along the road
+    let delivered = letters.delivered();
+    if delivered > run.delivered {
+        run.time_left += (delivered - run.delivered) as f32 * delivery_time(circuit.progress);
+        run.delivered = delivered;
+    }
+
+    run.time_left -= time.delta_seconds();
+    if run.time_left <= 0. {
+        run.time_left = 0.;
+        info_text.set("Time's up!");
+        next_state.set(GameState::End);
+    }
+}
+
+fn end_on_crash(
+    player_query: Query<&Health, With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if player_query
+        .get_single()
+        .is_ok_and(|health| health.hits > 0)
+    {
+        next_state.set(GameState::End);
+    }
+}
+
+fn update_endless_text(
+    run: Res<EndlessRun>,
+    player_query: Query<&Circuit, With<Player>>,
+    mut text_query: Query<&mut Text, With<EndlessText>>,
+) {
+    if let (Ok(mut text), Ok(circuit)) = (text_query.get_single_mut(), player_query.get_single()) {
+        text.sections[0].value = format!(
+            "Distance {} - Time left {:.1}",
+            circuit.progress, run.time_left
+        );
+    }
+}
+
+fn save_endless_run(
+    mut game_save: ResMut<GameSave>,
+    player_query: Query<(&Circuit, &Letters), With<Player>>,
+) {
+    let Ok((circuit, letters)) = player_query.get_single() else {
+        return;
+    };
+    let record = EndlessRecord {
+        score: endless_score(circuit.progress, letters),
+        distance: circuit.progress,
+        letters: letters.delivered(),
+        date: now(),
+    };
+
+    let new_best = game_save
+        .endless_best
+        .as_ref()
+        .is_none_or(|best| record.score > best.score);
+    if new_best {
+        game_save.endless_best = Some(record);
+        game_save.save();
+    }
+}
+
+fn spawn_endless_end_ui(
+    mut commands: Commands,
+    run: Res<EndlessRun>,
+    player_query: Query<(&Circuit, &Letters), With<Player>>,
+    aseprites: Res<AsepriteAssets>,
+) {
+    let Ok((circuit, letters)) = player_query.get_single() else {
+        return;
+    };
+    commands.trigger(PlaySoundtrack::Disable);
+
+    commands
+        .ui_root(RootAnchor::Center)
+        .insert((
+            StateScoped(Screen::Playing),
+            BackgroundColor(BACKGROUND.with_alpha(0.2)),
+        ))
+        .with_children(|children| {
+            let score = endless_score(circuit.progress, letters);
+            let message = if run.time_left <= 0. {
+                "Time's up"
+            } else {
+                "Crashed"
+            };
+
+            children.heading(message, HeadingSize::H3);
+            children.label(format!("Score: {}", score));
+            children.label(format!(
+                "Distance: {} - Delivered: {} - Missed: {}",
+                circuit.progress,
+                letters.delivered(),
+                letters.lost
+            ));
+
+            match &run.previous_best {
+                Some(best) if best.score >= score => {
+                    children.label(format!(
+                        "Best: {} - Distance: {}",
+                        best.score, best.distance
+                    ));
+                }
+                _ => {
+                    children.label("New record!");
+                }
+            }
+
+            children
+                .button_sprite("Restart", aseprites.get("button"), None)
+                .insert(EndAction::Restart);
+            children
+                .button_sprite("Menu", aseprites.get("button"), None)
+                .insert(EndAction::Menu);
+        });
+}
+
+fn leave_endless_mode(mut commands: Commands) {
+    commands.remove_resource::<EndlessMode>();
+}
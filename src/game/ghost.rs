@@ -0,0 +1,238 @@
+use std::fs::read_to_string;
+
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::{Animation, AnimationRepeat, AsepriteAnimationBundle};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    screen::{playing::CurrentLevel, Screen},
+    utils::{get_asset_path, path_exist},
+};
+
+use super::{
+    assets::handles::AsepriteAssets,
+    circuit::{Circuit, CircuitDirection, CircuitDuration},
+    save::{is_level_run, GameSave, RecordFileError},
+    spawn::player::Player,
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(GhostRecorder, Ghost)>();
+    app.add_systems(OnEnter(Screen::Playing), spawn_ghost.run_if(is_level_run));
+    app.add_systems(OnEnter(GameState::End), save_ghost.run_if(is_level_run));
+    app.add_systems(
+        FixedUpdate,
+        (record_ghost, play_ghost)
+            .run_if(in_state(GameState::Run).and_then(in_state(Screen::Playing))),
+    );
+}
+
+const GHOST_ALPHA: f32 = 0.4;
+
+/// Player state of one fixed tick
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct GhostFrame {
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    /// Animation tag, only stored when it changes
+    pub tag: Option<String>,
+}
+
+/// A whole run of a level, tick by tick
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct GhostRun {
+    pub duration: f32,
+    pub frames: Vec<GhostFrame>,
+}
+
+impl GhostRun {
+    /// Each direction of a level has its own best run
+    fn path(level_name: &str, direction: CircuitDirection) -> String {
+        get_asset_path(&format!(
+            "data/ghosts/{}-{}.ron",
+            level_name,
+            direction.name()
+        ))
+    }
+
+    pub fn load(level_name: &str, direction: CircuitDirection) -> Option<Self> {
+        let path = Self::path(level_name, direction);
+        if !path_exist(&path) {
+            return None;
+        }
+
+        ron::from_str(&read_to_string(path).ok()?).ok()
+    }
+
+    pub fn save(
+        &self,
+        level_name: &str,
+        direction: CircuitDirection,
+    ) -> Result<(), RecordFileError> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            super::save::write_ron(&Self::path(level_name, direction), self)?;
+        }
+        Ok(())
+    }
+}
+
+/// Frames of the current run
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct GhostRecorder {
+    pub frames: Vec<GhostFrame>,
+    last_tag: Option<String>,
+}
+
+/// Translucent postman replaying the best run, shown once the player rides its direction
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Ghost {
+    pub run: GhostRun,
+    pub frame: usize,
+    pub direction: CircuitDirection,
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    game_save: Res<GameSave>,
+    current_level: Res<CurrentLevel>,
+    aseprite_handles: Res<AsepriteAssets>,
+) {
+    if game_save.hide_ghost {
+        return;
+    }
+
+    let Some(level_name) = game_save.level_name(current_level.indice) else {
+        return;
+    };
+
+    // the direction is chosen once riding, both ghosts start with the run
+    for direction in [CircuitDirection::Clockwise, CircuitDirection::AntiClockwise] {
+        let Some(run) = GhostRun::load(level_name, direction) else {
+            continue;
+        };
+        let Some(first) = run.frames.first() else {
+            continue;
+        };
+
+        commands.spawn((
+            Name::new("Ghost"),
+            AsepriteAnimationBundle {
+                aseprite: aseprite_handles.get("postman"),
+                animation: Animation::default().with_tag("ride"),
+                transform: Transform::from_xyz(first.x, first.y, 0.09)
+                    .with_rotation(Quat::from_rotation_z(first.rotation))
+                    .with_scale(Vec3::new(1.5, 1.5, 0.)),
+                sprite: Sprite {
+                    color: Color::srgba(1., 1., 1., GHOST_ALPHA),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Ghost {
+                run,
+                frame: 0,
+                direction,
+            },
+            StateScoped(Screen::Playing),
+        ));
+    }
+}
+
+fn record_ghost(
+    mut recorder: ResMut<GhostRecorder>,
+    player_query: Query<(&Transform, &Animation), With<Player>>,
+) {
+    if let Ok((transform, animation)) = player_query.get_single() {
+        let tag = if animation.tag != recorder.last_tag {
+            recorder.last_tag.clone_from(&animation.tag);
+            animation.tag.clone()
+        } else {
+            None
+        };
+
+        recorder.frames.push(GhostFrame {
+            x: transform.translation.x,
+            y: transform.translation.y,
+            rotation: transform.rotation.to_euler(EulerRot::XYZ).2,
+            tag,
+        });
+    }
+}
+
+fn play_ghost(
+    mut commands: Commands,
+    player_query: Query<&Circuit, With<Player>>,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Animation,
+        &mut Visibility,
+        &mut Ghost,
+    )>,
+) {
+    let chosen = player_query
+        .get_single()
+        .ok()
+        .filter(|circuit| circuit.direction_chosen)
+        .map(|circuit| circuit.direction);
+
+    for (entity, mut transform, mut animation, mut visibility, mut ghost) in query.iter_mut() {
+        if chosen.is_some_and(|direction| direction != ghost.direction) {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let Some(frame) = ghost.run.frames.get(ghost.frame) else {
+            // the best run is over
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        if chosen.is_some() {
+            *visibility = Visibility::Inherited;
+        }
+
+        transform.translation.x = frame.x;
+        transform.translation.y = frame.y;
+        transform.rotation = Quat::from_rotation_z(frame.rotation);
+        if let Some(tag) = &frame.tag {
+            animation.play(tag, AnimationRepeat::Loop);
+        }
+
+        ghost.frame += 1;
+    }
+}
+
+/// Keep the run when it beats the saved one
+fn save_ghost(
+    mut recorder: ResMut<GhostRecorder>,
+    player_query: Query<(&Circuit, &CircuitDuration), With<Player>>,
+    game_save: Res<GameSave>,
+    current_level: Res<CurrentLevel>,
+) {
+    let Some(level_name) = game_save.level_name(current_level.indice) else {
+        return;
+    };
+    let Ok((circuit, circuit_duration)) = player_query.get_single() else {
+        return;
+    };
+    if !circuit.finished() {
+        return;
+    }
+
+    let best = GhostRun::load(level_name, circuit.direction);
+    if best.is_none_or(|best| circuit_duration.total < best.duration) {
+        let run = GhostRun {
+            duration: circuit_duration.total,
+            frames: std::mem::take(&mut recorder.frames),
+        };
+        if let Err(error) = run.save(level_name, circuit.direction) {
+            warn!("Could not save the ghost: {}", error);
+        }
+    }
+}
@@ -1,36 +1,30 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
-use bevy_aseprite_ultra::prelude::{Animation, AnimationRepeat, AsepriteAnimationBundle};
-use rand::Rng;
 
 use crate::{screen::Screen, AppSet};
 
 use super::{
-    assets::handles::AsepriteAssets,
     circuit::{Circuit, CircuitDirection},
     collider::Collider,
+    letter::LaunchZoneMirror,
     map::chunk::PIXEL_CHUNK_SIZE,
-    restart::Restart,
-    spawn::{
-        map::{ChunkTag, FollowPlayerRotation, ObstacleTag, PostOffice},
-        player::{Player, PlayerController},
-    },
-    ui::InfoText,
+    spawn::{map::FollowPlayerRotation, player::PlayerSlot},
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<HouseOrientation>();
+    app.register_type::<(HouseOrientation, Oriented)>();
     app.add_systems(
         Update,
-        ((rotate_house, follow_player_rotation, obstacle_check).in_set(AppSet::Update),)
+        ((rotate_house, follow_player_rotation).in_set(AppSet::Update),)
             .run_if(in_state(Screen::Playing)),
     );
 }
 
-#[derive(Resource, Default, Reflect)]
-#[reflect(Resource)]
-pub struct HouseRotate(pub bool);
+/// Houses and launch zones already placed for the direction of the ride
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Oriented;
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
@@ -38,20 +32,25 @@ pub struct HouseOrientation {
     pub angle_mul: i32,
 }
 
+/// Center of a house chunk from its origin tile
+const HOUSE_CHUNK_CENTER: Vec3 =
+    Vec3::new(PIXEL_CHUNK_SIZE / 2. - 8., -PIXEL_CHUNK_SIZE / 2. + 8., 0.);
+
 pub fn rotate_house(
-    mut house_query: Query<(&mut Transform, &mut HouseOrientation)>,
-    circuit: Res<Circuit>,
-    mut house_rotate: ResMut<HouseRotate>,
+    mut commands: Commands,
+    mut house_query: Query<(Entity, &mut Transform, &HouseOrientation), Without<Oriented>>,
+    mut zone_query: Query<(Entity, &mut Collider, &LaunchZoneMirror), Without<Oriented>>,
+    player_query: Query<(&Circuit, &PlayerSlot)>,
 ) {
-    if circuit.direction_chosen && !house_rotate.0 {
-        for (mut transform, mut orientation) in house_query.iter_mut() {
+    let Some(circuit) = first_player_circuit(&player_query) else {
+        return;
+    };
+
+    // houses spawned later, in the endless mode, are placed when they appear
+    if circuit.direction_chosen {
+        for (entity, mut transform, orientation) in house_query.iter_mut() {
             let rotation = (PI / 2.) * orientation.angle_mul as f32;
-            if circuit.direction == CircuitDirection::AntiClockwise {
-                transform.rotate_z(rotation);
-            }
-            if circuit.direction == CircuitDirection::Clockwise {
-                transform.rotate_z(-rotation);
-            }
+            transform.rotate_z(-rotation);
 
             if orientation.angle_mul == 0 {
                 transform.translation += Vec3::X * 16.;
@@ -63,34 +62,41 @@ pub fn rotate_house(
                 transform.translation -= Vec3::Y * PIXEL_CHUNK_SIZE - 32.;
             }
 
+            if circuit.direction == CircuitDirection::AntiClockwise {
+                // same place, turned around to face the other way
+                let center = transform.rotation * HOUSE_CHUNK_CENTER;
+                transform.rotate_z(PI);
+                transform.translation += 2. * center;
+            }
+
             transform.translation.z = 0.05;
+            commands.entity(entity).insert(Oriented);
         }
 
-        house_rotate.0 = true;
+        // launch zones are placed for a clockwise ride
+        for (entity, mut collider, mirror) in zone_query.iter_mut() {
+            if circuit.direction == CircuitDirection::AntiClockwise {
+                let center = collider.center();
+                collider.pos(mirror.mirror(center));
+            }
+            commands.entity(entity).insert(Oriented);
+        }
     }
 }
 
 fn follow_player_rotation(
     time: Res<Time>,
-    circuit: Res<Circuit>,
+    player_query: Query<(&Circuit, &PlayerSlot)>,
     mut query: Query<&mut Transform, With<FollowPlayerRotation>>,
 ) {
+    let Some(circuit) = first_player_circuit(&player_query) else {
+        return;
+    };
+
     if !circuit.in_turn {
         for mut transform in query.iter_mut() {
-            let mut reset_angle = match circuit.direction {
-                CircuitDirection::AntiClockwise => PI,
-                CircuitDirection::Clockwise => 0.,
-            };
-
-            let turn = (circuit.turn_count) as f32 * PI / 2.;
-
-            reset_angle += match circuit.direction {
-                CircuitDirection::AntiClockwise => -turn,
-                CircuitDirection::Clockwise => -turn,
-            };
-
             transform.rotation = transform.rotation.lerp(
-                Quat::from_axis_angle(Vec3::Z, reset_angle),
+                Quat::from_axis_angle(Vec3::Z, circuit.expected_angle()),
                 time.delta_seconds() * 30.,
             );
 
@@ -99,31 +105,12 @@ fn follow_player_rotation(
     }
 }
 
-fn obstacle_check(
-    time: Res<Time>,
-    mut commands: Commands,
-    mut player_query: Query<(&Collider, &mut Animation, &mut PlayerController), With<Player>>,
-    obstacles_query: Query<&Collider, (With<ObstacleTag>, Without<Player>, Without<ChunkTag>)>,
-    mut info_text: ResMut<InfoText>,
-) {
-    if let Ok((player_collider, mut animation, mut controller)) = player_query.get_single_mut() {
-        controller.start_timer.tick(time.delta());
-        if !controller.damn && controller.start_timer.finished() {
-            for obstacle_collider in obstacles_query.iter() {
-                if player_collider.collide(obstacle_collider) {
-                    controller.damn = true;
-                    animation.play("fall", AnimationRepeat::Count(0));
-                    animation.clear_queue();
-                }
-            }
-        }
-
-        if controller.damn {
-            controller.obstacle_timer.tick(time.delta());
-            info_text.set("Oh no! You've broken a leg!");
-            if controller.obstacle_timer.finished() {
-                commands.trigger(Restart);
-            }
-        }
-    }
+/// The map is shared, it follows the ride of the first player
+fn first_player_circuit<'a>(
+    player_query: &'a Query<(&Circuit, &PlayerSlot)>,
+) -> Option<&'a Circuit> {
+    player_query
+        .iter()
+        .find(|(_, slot)| slot.0 == 0)
+        .map(|(circuit, _)| circuit)
 }
@@ -0,0 +1,457 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::screen::Screen;
+
+use super::{
+    movements::player_movements,
+    replay::ReplayPlayback,
+    rival::Rival,
+    save::GameSave,
+    spawn::player::{Health, Player, PlayerSlot},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PlayerInput>();
+    app.configure_sets(FixedUpdate, InputSet.before(player_movements));
+    app.add_systems(
+        FixedUpdate,
+        (read_keyboard, read_gamepads)
+            .chain()
+            .in_set(InputSet)
+            .run_if(in_state(Screen::Playing).and_then(not(resource_exists::<ReplayPlayback>))),
+    );
+    app.add_systems(Update, rumble_on_crash.run_if(in_state(Screen::Playing)));
+}
+
+/// Systems filling `PlayerInput` before the player moves
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputSet;
+
+/// Buttons held by a player during a fixed tick
+#[derive(Component, Reflect, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct PlayerInput {
+    pub forward: bool,
+    pub brake: bool,
+    pub left: bool,
+    pub right: bool,
+    pub launch: bool,
+    /// Held to hand over a parcel
+    #[serde(default)]
+    pub deliver: bool,
+    /// Grab the outgoing mail of a letter box
+    #[serde(default)]
+    pub collect: bool,
+    /// Analog steering of a stick, from -1 on the right to 1 on the left
+    #[serde(default)]
+    pub steer: f32,
+    /// Analog throttle of a trigger, from 0 to 1
+    #[serde(default)]
+    pub throttle: f32,
+}
+
+impl PlayerInput {
+    /// Rotation asked, the stick when it is held, else the buttons
+    pub fn steering(&self) -> f32 {
+        if self.steer != 0. {
+            self.steer.clamp(-1., 1.)
+        } else if self.left {
+            1.
+        } else if self.right {
+            -1.
+        } else {
+            0.
+        }
+    }
+
+    /// Part of the acceleration asked
+    pub fn throttle(&self) -> f32 {
+        if self.forward {
+            1.
+        } else {
+            self.throttle.clamp(0., 1.)
+        }
+    }
+}
+
+/// What a key can be bound to
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Accelerate,
+    Brake,
+    SteerLeft,
+    SteerRight,
+    Throw,
+    Deliver,
+    Collect,
+    Restart,
+    /// Leaves the run for the title screen
+    Pause,
+}
+
+impl Action {
+    /// Actions of each local player
+    pub const RIDE: [Action; 7] = [
+        Action::Accelerate,
+        Action::Brake,
+        Action::SteerLeft,
+        Action::SteerRight,
+        Action::Throw,
+        Action::Deliver,
+        Action::Collect,
+    ];
+    /// Actions shared by the local players, bound on the first one
+    pub const GLOBAL: [Action; 2] = [Action::Restart, Action::Pause];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Accelerate => "Accelerate",
+            Action::Brake => "Brake",
+            Action::SteerLeft => "Steer left",
+            Action::SteerRight => "Steer right",
+            Action::Throw => "Throw",
+            Action::Deliver => "Deliver",
+            Action::Collect => "Collect",
+            Action::Restart => "Restart",
+            Action::Pause => "Pause",
+        }
+    }
+
+    /// Fixed button of a gamepad, the stick and the triggers also ride
+    pub fn gamepad_button(&self) -> GamepadButtonType {
+        match self {
+            Action::Accelerate => GamepadButtonType::RightTrigger2,
+            Action::Brake => GamepadButtonType::LeftTrigger2,
+            Action::SteerLeft => GamepadButtonType::DPadLeft,
+            Action::SteerRight => GamepadButtonType::DPadRight,
+            Action::Throw => GamepadButtonType::South,
+            Action::Deliver => GamepadButtonType::West,
+            Action::Collect => GamepadButtonType::North,
+            Action::Restart => GamepadButtonType::Select,
+            Action::Pause => GamepadButtonType::Start,
+        }
+    }
+}
+
+/// Stick tilt under which the bike keeps straight
+const STICK_DEADZONE: f32 = 0.15;
+/// Trigger travel from which the bike brakes
+const BRAKE_THRESHOLD: f32 = 0.3;
+const RUMBLE_DURATION: Duration = Duration::from_millis(300);
+
+/// Gamepad of a local player, in connection order
+pub fn slot_gamepad(gamepads: &Gamepads, slot: usize) -> Option<Gamepad> {
+    let mut connected: Vec<_> = gamepads.iter().collect();
+    connected.sort_by_key(|gamepad| gamepad.id);
+    connected.get(slot).copied()
+}
+
+/// Keys of the local players, by player slot, saved with the settings
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    pub slots: Vec<HashMap<Action, KeyCode>>,
+}
+
+impl Default for KeyBindings {
+    /// The second player rides on the arrows
+    fn default() -> Self {
+        Self {
+            slots: vec![
+                HashMap::from_iter([
+                    (Action::Accelerate, KeyCode::KeyW),
+                    (Action::Brake, KeyCode::KeyS),
+                    (Action::SteerLeft, KeyCode::KeyA),
+                    (Action::SteerRight, KeyCode::KeyD),
+                    (Action::Throw, KeyCode::Space),
+                    (Action::Deliver, KeyCode::KeyE),
+                    (Action::Collect, KeyCode::KeyF),
+                    (Action::Restart, KeyCode::KeyR),
+                    (Action::Pause, KeyCode::Escape),
+                ]),
+                HashMap::from_iter([
+                    (Action::Accelerate, KeyCode::ArrowUp),
+                    (Action::Brake, KeyCode::ArrowDown),
+                    (Action::SteerLeft, KeyCode::ArrowLeft),
+                    (Action::SteerRight, KeyCode::ArrowRight),
+                    (Action::Throw, KeyCode::Enter),
+                    (Action::Deliver, KeyCode::ShiftRight),
+                    (Action::Collect, KeyCode::ControlRight),
+                ]),
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, slot: usize, action: Action) -> Option<KeyCode> {
+        self.slots.get(slot)?.get(&action).copied()
+    }
+
+    pub fn pressed(&self, keys: &ButtonInput<KeyCode>, slot: usize, action: Action) -> bool {
+        self.key(slot, action).is_some_and(|key| keys.pressed(key))
+    }
+
+    /// Pressed by any local player
+    pub fn any_pressed(&self, keys: &ButtonInput<KeyCode>, action: Action) -> bool {
+        (0..self.slots.len()).any(|slot| self.pressed(keys, slot, action))
+    }
+
+    pub fn any_just_pressed(&self, keys: &ButtonInput<KeyCode>, action: Action) -> bool {
+        (0..self.slots.len()).any(|slot| {
+            self.key(slot, action)
+                .is_some_and(|key| keys.just_pressed(key))
+        })
+    }
+
+    /// A key already bound elsewhere takes the previous key of the action
+    pub fn bind(&mut self, slot: usize, action: Action, key: KeyCode) {
+        if self.slots.len() <= slot {
+            self.slots.resize_with(slot + 1, HashMap::default);
+        }
+        let previous = self.key(slot, action);
+
+        for keys in self.slots.iter_mut() {
+            if let Some(previous) = previous {
+                for bound in keys.values_mut().filter(|bound| **bound == key) {
+                    *bound = previous;
+                }
+            }
+            keys.retain(|_, bound| *bound != key);
+        }
+
+        self.slots[slot].insert(action, key);
+    }
+}
+
+/// Key name shown to the players
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Run condition on a shared action, like the pause, from the keys or any gamepad
+pub fn action_just_pressed(
+    action: Action,
+) -> impl FnMut(Res<ButtonInput<KeyCode>>, Res<ButtonInput<GamepadButton>>, Res<GameSave>) -> bool + Clone
+{
+    move |keys: Res<ButtonInput<KeyCode>>,
+          buttons: Res<ButtonInput<GamepadButton>>,
+          game_save: Res<GameSave>| {
+        game_save.bindings.any_just_pressed(&keys, action)
+            || buttons
+                .get_just_pressed()
+                .any(|button| button.button_type == action.gamepad_button())
+    }
+}
+
+/// Held on the keys or any gamepad
+pub fn action_pressed(
+    keys: &ButtonInput<KeyCode>,
+    buttons: &ButtonInput<GamepadButton>,
+    game_save: &GameSave,
+    action: Action,
+) -> bool {
+    game_save.bindings.any_pressed(keys, action)
+        || buttons
+            .get_pressed()
+            .any(|button| button.button_type == action.gamepad_button())
+}
+
+pub fn read_keyboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    game_save: Res<GameSave>,
+    mut query: Query<(&mut PlayerInput, &PlayerSlot), With<Player>>,
+) {
+    let bindings = &game_save.bindings;
+
+    for (mut input, slot) in query.iter_mut() {
+        if bindings.slots.get(slot.0).is_none() {
+            continue;
+        }
+        let pressed = |action| bindings.pressed(&keys, slot.0, action);
+
+        *input = PlayerInput {
+            forward: pressed(Action::Accelerate),
+            brake: pressed(Action::Brake),
+            left: pressed(Action::SteerLeft),
+            right: pressed(Action::SteerRight),
+            launch: pressed(Action::Throw),
+            deliver: pressed(Action::Deliver),
+            collect: pressed(Action::Collect),
+            ..default()
+        };
+    }
+}
+
+/// Adds the gamepad of each local player to its keys
+pub fn read_gamepads(
+    gamepads: Res<Gamepads>,
+    game_save: Res<GameSave>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    button_axes: Res<Axis<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut query: Query<(&mut PlayerInput, &PlayerSlot), With<Player>>,
+) {
+    for (mut input, slot) in query.iter_mut() {
+        if game_save.bindings.slots.get(slot.0).is_none() {
+            continue;
+        }
+        let Some(gamepad) = slot_gamepad(&gamepads, slot.0) else {
+            continue;
+        };
+        let pressed =
+            |action: Action| buttons.pressed(GamepadButton::new(gamepad, action.gamepad_button()));
+        let trigger = |action: Action| {
+            button_axes
+                .get(GamepadButton::new(gamepad, action.gamepad_button()))
+                .unwrap_or(0.)
+        };
+
+        // the angle of the stick from straight ahead sets the rotation
+        let stick = Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.),
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.),
+        );
+        if stick.length() > STICK_DEADZONE {
+            input.steer = (-stick.x.atan2(stick.y.max(0.)) / FRAC_PI_2).clamp(-1., 1.);
+        }
+
+        input.throttle = input.throttle.max(trigger(Action::Accelerate));
+        input.forward |= pressed(Action::Accelerate);
+        input.brake |= pressed(Action::Brake) || trigger(Action::Brake) > BRAKE_THRESHOLD;
+        input.left |= pressed(Action::SteerLeft);
+        input.right |= pressed(Action::SteerRight);
+        input.launch |= pressed(Action::Throw);
+        input.deliver |= pressed(Action::Deliver);
+        input.collect |= pressed(Action::Collect);
+    }
+}
+
+/// A hit shakes the gamepad of the player
+fn rumble_on_crash(
+    gamepads: Res<Gamepads>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+    mut last_hits: Local<HashMap<Entity, i32>>,
+    player_query: Query<(Entity, &Health, &PlayerSlot), (With<Player>, Without<Rival>)>,
+) {
+    for (entity, health, slot) in player_query.iter() {
+        let last = last_hits.insert(entity, health.hits).unwrap_or(0);
+        if health.hits <= last {
+            continue;
+        }
+
+        if let Some(gamepad) = slot_gamepad(&gamepads, slot.0) {
+            rumble_requests.send(GamepadRumbleRequest::Add {
+                gamepad,
+                duration: RUMBLE_DURATION,
+                intensity: GamepadRumbleIntensity::MAX,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bind_free_key() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(0, Action::Throw, KeyCode::KeyQ);
+
+        assert_eq!(bindings.key(0, Action::Throw), Some(KeyCode::KeyQ));
+        assert_eq!(bindings.key(0, Action::Accelerate), Some(KeyCode::KeyW));
+        assert_eq!(bindings.key(1, Action::Throw), Some(KeyCode::Enter));
+    }
+
+    #[test]
+    fn test_bind_swaps_in_slot() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(0, Action::Accelerate, KeyCode::KeyS);
+
+        assert_eq!(bindings.key(0, Action::Accelerate), Some(KeyCode::KeyS));
+        assert_eq!(bindings.key(0, Action::Brake), Some(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn test_bind_swaps_across_slots() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(0, Action::Throw, KeyCode::ArrowUp);
+
+        assert_eq!(bindings.key(0, Action::Throw), Some(KeyCode::ArrowUp));
+        assert_eq!(bindings.key(1, Action::Accelerate), Some(KeyCode::Space));
+    }
+
+    #[test]
+    fn test_bind_same_key() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(0, Action::Brake, KeyCode::KeyS);
+
+        assert_eq!(bindings, KeyBindings::default());
+    }
+
+    #[test]
+    fn test_bind_unbound_action_takes_the_key() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(1, Action::Restart, KeyCode::KeyR);
+
+        assert_eq!(bindings.key(1, Action::Restart), Some(KeyCode::KeyR));
+        assert_eq!(bindings.key(0, Action::Restart), None);
+    }
+
+    #[test]
+    fn test_bind_new_slot() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(2, Action::Accelerate, KeyCode::KeyI);
+
+        assert_eq!(bindings.slots.len(), 3);
+        assert_eq!(bindings.key(2, Action::Accelerate), Some(KeyCode::KeyI));
+    }
+
+    #[test]
+    fn test_steering_buttons() {
+        let left = PlayerInput {
+            left: true,
+            ..default()
+        };
+        let right = PlayerInput {
+            right: true,
+            ..default()
+        };
+
+        assert_eq!(left.steering(), 1.);
+        assert_eq!(right.steering(), -1.);
+        assert_eq!(PlayerInput::default().steering(), 0.);
+    }
+
+    #[test]
+    fn test_steering_stick_first() {
+        let input = PlayerInput {
+            right: true,
+            steer: 0.5,
+            ..default()
+        };
+        assert_eq!(input.steering(), 0.5);
+    }
+
+    #[test]
+    fn test_steering_clamped() {
+        let input = PlayerInput {
+            steer: -3.,
+            ..default()
+        };
+        assert_eq!(input.steering(), -1.);
+    }
+}
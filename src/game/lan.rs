@@ -0,0 +1,470 @@
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::{Animation, AnimationRepeat, AsepriteAnimationBundle};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    screen::{playing::CurrentLevel, Screen},
+    ui::{palette::BACKGROUND, prelude::*},
+};
+
+use super::{
+    assets::handles::AsepriteAssets,
+    audio::soundtrack::PlaySoundtrack,
+    circuit::{Circuit, CircuitDuration},
+    spawn::player::{Player, PlayerSlot},
+    ui::EndAction,
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(LanRace, RemotePostman)>();
+    app.add_systems(OnEnter(Screen::Title), leave_lan);
+    app.add_systems(
+        Update,
+        (
+            receive_messages,
+            call_host.run_if(in_state(Screen::Title)),
+            update_lan_status.run_if(in_state(Screen::Title)),
+        )
+            .chain()
+            .run_if(resource_exists::<LanSession>),
+    );
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        spawn_remote_postman.run_if(resource_exists::<LanRace>),
+    );
+    app.add_systems(
+        Update,
+        (
+            send_state,
+            interpolate_remote_postman,
+            update_lan_results.run_if(in_state(GameState::EndScreen)),
+        )
+            .after(receive_messages)
+            .run_if(in_state(Screen::Playing).and_then(resource_exists::<LanRace>)),
+    );
+    app.add_systems(
+        OnEnter(GameState::EndScreen),
+        spawn_lan_results.run_if(resource_exists::<LanRace>),
+    );
+}
+
+/// Port the host listens on
+pub const LAN_PORT: u16 = 7777;
+/// Delay between two calls of a guest looking for a host, in seconds
+const HELLO_INTERVAL: f32 = 0.5;
+/// Delay between two states sent to the other instance, in seconds
+const STATE_INTERVAL: f32 = 1. / 20.;
+/// The remote postman is drawn that late, between two received states, in seconds
+const INTERPOLATION_DELAY: f64 = 0.1;
+const MAX_MESSAGE_SIZE: usize = 1024;
+
+/// What the two instances tell each other, as JSON in UDP packets
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LanMessage {
+    /// A guest looks for a host
+    Hello,
+    /// The host picked the race, both instances build the same map from it
+    Start { level: i32, seed: u64 },
+    /// Where the postman is, its finish time is repeated once it finished
+    State {
+        x: f32,
+        y: f32,
+        rotation: f32,
+        tag: Option<String>,
+        finish: Option<f32>,
+    },
+    /// The other postman went back to the menu
+    Bye,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum LanRole {
+    Host,
+    Guest,
+}
+
+/// Socket of a race on the local network, the host waits for one guest
+#[derive(Resource)]
+pub struct LanSession {
+    socket: UdpSocket,
+    pub role: LanRole,
+    /// Address of the other instance, once it answered
+    pub peer: Option<SocketAddr>,
+    timer: Timer,
+}
+
+impl LanSession {
+    pub fn host() -> io::Result<Self> {
+        Self::bind(LAN_PORT, LanRole::Host, STATE_INTERVAL)
+    }
+
+    pub fn join() -> io::Result<Self> {
+        let session = Self::bind(0, LanRole::Guest, HELLO_INTERVAL)?;
+        session.socket.set_broadcast(true)?;
+        Ok(session)
+    }
+
+    fn bind(port: u16, role: LanRole, interval: f32) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            role,
+            peer: None,
+            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+        })
+    }
+
+    fn send_to(&self, message: &LanMessage, address: SocketAddr) {
+        if let Ok(bytes) = serde_json::to_vec(message) {
+            // lost packets are covered by the next ones
+            let _ = self.socket.send_to(&bytes, address);
+        }
+    }
+
+    pub fn send(&self, message: &LanMessage) {
+        if let Some(peer) = self.peer {
+            self.send_to(message, peer);
+        }
+    }
+
+    /// Messages arrived since the last call
+    fn receive(&self) -> Vec<(SocketAddr, LanMessage)> {
+        let mut messages = Vec::new();
+        let mut buffer = [0; MAX_MESSAGE_SIZE];
+
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, from)) => {
+                    if let Ok(message) = serde_json::from_slice(&buffer[..size]) {
+                        messages.push((from, message));
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    warn!("LAN: {}", error);
+                    break;
+                }
+            }
+        }
+
+        messages
+    }
+}
+
+/// Race shared with the other instance
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct LanRace {
+    pub level: i32,
+    pub seed: u64,
+    /// Finish time of the other postman
+    pub remote_finish: Option<f32>,
+    /// The other postman went back to the menu
+    pub remote_left: bool,
+}
+
+/// Postman of the other instance, drawn from the states it sends
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct RemotePostman {
+    /// Received positions and rotations, by arrival time
+    snapshots: VecDeque<(f64, Vec2, f32)>,
+    tag: Option<String>,
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct LanStatus;
+
+/// Line of the results, by rank
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+struct LanResultLine(usize);
+
+fn leave_lan(mut commands: Commands, session: Option<Res<LanSession>>) {
+    if let Some(session) = session {
+        session.send(&LanMessage::Bye);
+    }
+    commands.remove_resource::<LanSession>();
+    commands.remove_resource::<LanRace>();
+}
+
+fn receive_messages(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut session: ResMut<LanSession>,
+    mut race: Option<ResMut<LanRace>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut remote_query: Query<&mut RemotePostman>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let mut started = race.as_ref().map(|race| (race.level, race.seed));
+
+    for (from, message) in session.receive() {
+        match message {
+            LanMessage::Hello if session.role == LanRole::Host => {
+                if session.peer.is_some_and(|peer| peer != from) {
+                    continue;
+                }
+                session.peer = Some(from);
+
+                // the start is sent again to a guest that missed it
+                let (level, seed) = *started.get_or_insert_with(|| {
+                    let seed = rand::random();
+                    commands.insert_resource(LanRace {
+                        level: current_level.indice,
+                        seed,
+                        ..default()
+                    });
+                    next_screen.set(Screen::Playing);
+                    (current_level.indice, seed)
+                });
+                session.send(&LanMessage::Start { level, seed });
+            }
+            LanMessage::Start { level, seed } if session.role == LanRole::Guest => {
+                if started.is_some() || session.peer.is_some() {
+                    continue;
+                }
+                started = Some((level, seed));
+                session.peer = Some(from);
+                // no more calls, the timer now paces the states
+                session.timer = Timer::from_seconds(STATE_INTERVAL, TimerMode::Repeating);
+                current_level.indice = level;
+                commands.insert_resource(LanRace {
+                    level,
+                    seed,
+                    ..default()
+                });
+                next_screen.set(Screen::Playing);
+            }
+            LanMessage::State {
+                x,
+                y,
+                rotation,
+                tag,
+                finish,
+            } if session.peer == Some(from) => {
+                if let Some(race) = race.as_mut() {
+                    race.remote_finish = finish;
+                }
+                for mut remote in remote_query.iter_mut() {
+                    remote.snapshots.push_back((now, Vec2::new(x, y), rotation));
+                    remote.tag.clone_from(&tag);
+                }
+            }
+            LanMessage::Bye if session.peer == Some(from) => {
+                if let Some(race) = race.as_mut() {
+                    race.remote_left = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A guest calls on the local network and on this machine until a host answers
+fn call_host(time: Res<Time<Real>>, mut session: ResMut<LanSession>) {
+    if session.role != LanRole::Guest || session.peer.is_some() {
+        return;
+    }
+
+    session.timer.tick(time.delta());
+    if session.timer.just_finished() {
+        for ip in [Ipv4Addr::BROADCAST, Ipv4Addr::LOCALHOST] {
+            session.send_to(&LanMessage::Hello, SocketAddr::from((ip, LAN_PORT)));
+        }
+    }
+}
+
+fn update_lan_status(
+    session: Res<LanSession>,
+    status_query: Query<&Children, With<LanStatus>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let status = match session.role {
+        LanRole::Host => format!("Waiting for a guest on port {}...", LAN_PORT),
+        LanRole::Guest => "Looking for a host...".into(),
+    };
+
+    for children in status_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != status {
+                    text.sections[0].value.clone_from(&status);
+                }
+            }
+        }
+    }
+}
+
+fn spawn_remote_postman(mut commands: Commands, aseprite_handles: Res<AsepriteAssets>) {
+    commands.spawn((
+        Name::new("Remote Postman"),
+        AsepriteAnimationBundle {
+            aseprite: aseprite_handles.get("postman"),
+            animation: Animation::default().with_tag("ride"),
+            transform: Transform::from_xyz(0., 0., 0.09).with_scale(Vec3::new(1.5, 1.5, 0.)),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        RemotePostman::default(),
+        StateScoped(Screen::Playing),
+    ));
+}
+
+fn send_state(
+    time: Res<Time<Real>>,
+    mut session: ResMut<LanSession>,
+    player_query: Query<
+        (
+            &Transform,
+            &Animation,
+            &Circuit,
+            &CircuitDuration,
+            &PlayerSlot,
+        ),
+        With<Player>,
+    >,
+) {
+    session.timer.tick(time.delta());
+    if !session.timer.just_finished() {
+        return;
+    }
+
+    let Some((transform, animation, circuit, circuit_duration, _)) =
+        player_query.iter().find(|(.., slot)| slot.0 == 0)
+    else {
+        return;
+    };
+
+    session.send(&LanMessage::State {
+        x: transform.translation.x,
+        y: transform.translation.y,
+        rotation: transform.rotation.to_euler(EulerRot::XYZ).2,
+        tag: animation.tag.clone(),
+        finish: circuit.finished().then_some(circuit_duration.total),
+    });
+}
+
+/// Drawn a little in the past, between the two states around that time
+fn interpolate_remote_postman(
+    time: Res<Time<Real>>,
+    mut remote_query: Query<(
+        &mut Transform,
+        &mut Animation,
+        &mut Visibility,
+        &mut RemotePostman,
+    )>,
+) {
+    let render_time = time.elapsed_seconds_f64() - INTERPOLATION_DELAY;
+
+    for (mut transform, mut animation, mut visibility, mut remote) in remote_query.iter_mut() {
+        while remote.snapshots.len() > 2 && remote.snapshots[1].0 <= render_time {
+            remote.snapshots.pop_front();
+        }
+
+        let (position, rotation) = match (remote.snapshots.front(), remote.snapshots.get(1)) {
+            (Some(from), Some(to)) if to.0 > from.0 => {
+                let t = ((render_time - from.0) / (to.0 - from.0)).clamp(0., 1.) as f32;
+                (
+                    from.1.lerp(to.1, t),
+                    Quat::from_rotation_z(from.2).slerp(Quat::from_rotation_z(to.2), t),
+                )
+            }
+            (Some(from), _) => (from.1, Quat::from_rotation_z(from.2)),
+            _ => continue,
+        };
+
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotation = rotation;
+        *visibility = Visibility::Inherited;
+
+        if remote.tag.is_some() && animation.tag != remote.tag {
+            if let Some(tag) = remote.tag.clone() {
+                animation.play(&tag, AnimationRepeat::Loop);
+            }
+        }
+    }
+}
+
+fn spawn_lan_results(mut commands: Commands, aseprites: Res<AsepriteAssets>) {
+    commands.trigger(PlaySoundtrack::Disable);
+
+    commands
+        .ui_root(RootAnchor::Center)
+        .insert((
+            StateScoped(Screen::Playing),
+            BackgroundColor(BACKGROUND.with_alpha(0.2)),
+        ))
+        .with_children(|children| {
+            children.heading("LAN Race", HeadingSize::H3);
+            for rank in 0..2 {
+                children.label("").insert(LanResultLine(rank));
+            }
+
+            children
+                .button_sprite("Menu", aseprites.get("button"), None)
+                .insert(EndAction::Menu);
+        });
+}
+
+/// Finish times ranked, the other postman may still be riding
+fn update_lan_results(
+    race: Res<LanRace>,
+    session: Res<LanSession>,
+    player_query: Query<(&Circuit, &CircuitDuration, &PlayerSlot), With<Player>>,
+    line_query: Query<(&Children, &LanResultLine)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let local_finish = player_query.iter().find(|(.., slot)| slot.0 == 0).and_then(
+        |(circuit, circuit_duration, _)| circuit.finished().then_some(circuit_duration.total),
+    );
+    let (local_name, remote_name) = match session.role {
+        LanRole::Host => ("Player 1 (you)", "Player 2"),
+        LanRole::Guest => ("Player 2 (you)", "Player 1"),
+    };
+    let remote_status = if race.remote_left {
+        "left the race"
+    } else {
+        "still riding..."
+    };
+
+    let mut results = [
+        (local_name, local_finish, "crashed out"),
+        (remote_name, race.remote_finish, remote_status),
+    ];
+    results.sort_by(|a, b| match (a.1, b.1) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    for (children, line) in line_query.iter() {
+        let Some((name, finish, status)) = results.get(line.0) else {
+            continue;
+        };
+        let value = match finish {
+            Some(time) => format!("{}. {} - {:.2}", line.0 + 1, name, time),
+            None => format!("{}. {} - {}", line.0 + 1, name, status),
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != value {
+                    text.sections[0].value.clone_from(&value);
+                }
+            }
+        }
+    }
+}
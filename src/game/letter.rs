@@ -10,20 +10,30 @@ use crate::{
 };
 
 use super::{
-    assets::handles::AsepriteAssets,
+    assets::handles::{AsepriteAssets, FontAssets},
     audio::sfx::PlaySfx,
+    circuit::{Circuit, CircuitDirection},
     collider::Collider,
+    input::{InputSet, PlayerInput},
+    mail::OutgoingMail,
+    movements::Velocity,
+    rival::Rival,
     spawn::player::{self, Player, PlayerController},
-    ui::InfoText,
+    ui::{HudOf, InfoText},
 };
 
 pub(super) fn plugin(app: &mut App) {
+    // houses, one tuple can't register them all
+    app.register_type::<(Address, Delivery, DeliveryKind, MissedMarker)>();
     app.register_type::<(
         Letters,
         LetterBox,
         LetterTarget,
         Letter,
+        LostLetter,
+        HouseWall,
         LetterLaunchZone,
+        LaunchZoneMirror,
         LetterUi,
     )>();
 
@@ -31,25 +41,55 @@ pub(super) fn plugin(app: &mut App) {
         Update,
         (
             update_letter_ui,
+            order_letters,
+            mark_missed_houses,
+            animate_letter_box,
+        )
+            .run_if(in_state(Screen::Playing)),
+    );
+    // throws read the input of the tick, whatever the frame rate
+    app.add_systems(
+        FixedUpdate,
+        (
             (
                 launch_zone_detection,
-                animate_letter_box,
                 launch_letter,
+                fly_letters,
                 remove_letter,
             )
-                .chain(),
+                .chain()
+                .after(InputSet),
+            fade_lost_letters,
         )
             .run_if(in_state(Screen::Playing)),
     );
-    app.add_systems(FixedUpdate, (move_letter).run_if(in_state(Screen::Playing)));
 }
 
-#[derive(Resource, Reflect, Debug, Default, PartialEq, Eq, Clone)]
-#[reflect(Resource)]
+/// Bag and deliveries of a postman
+#[derive(Component, Reflect, Debug, Default, PartialEq, Eq, Clone)]
+#[reflect(Component)]
 pub struct Letters {
     pub all: i32,
     pub to_post: i32,
     pub lost: i32,
+    /// Parcels handed over, they are part of the delivered letters
+    pub parcels: i32,
+    /// Addresses of the letters in the bag, the next one to throw first
+    pub queue: Vec<u32>,
+    /// Letters thrown into the wrong letter box
+    pub wrong: i32,
+    /// The queue follows the direction of the run
+    pub ordered: bool,
+    /// Letter boxes holding outgoing mail
+    pub mail: i32,
+    /// Outgoing mail on the bike
+    pub mail_carried: i32,
+    /// Outgoing mail brought back to the post office
+    pub mail_returned: i32,
+    /// Addresses of the houses passed without a delivery
+    pub missed: Vec<u32>,
+    /// Houses another postman delivered first
+    pub taken: i32,
 }
 
 impl Letters {
@@ -57,23 +97,170 @@ impl Letters {
         Self {
             all: letters,
             to_post: letters,
-            lost: 0,
+            ..default()
+        }
+    }
+
+    pub fn with_queue(mut self, queue: Vec<u32>) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    pub fn with_mail(mut self, mail: i32) -> Self {
+        self.mail = mail;
+        self
+    }
+
+    /// Address of the next letter to throw
+    pub fn next(&self) -> Option<u32> {
+        self.queue.first().copied()
+    }
+
+    pub fn remove_address(&mut self, address: u32) {
+        self.queue.retain(|queued| *queued != address);
+    }
+
+    /// The postman passed the house without delivering it
+    pub fn miss(&mut self, address: u32) {
+        self.remove_address(address);
+        if !self.missed.contains(&address) {
+            self.missed.push(address);
         }
+        self.lost = self.missed.len() as i32;
     }
+
+    /// The postman delivered the house, even one missed on a previous lap
+    pub fn deliver(&mut self, address: u32) {
+        self.remove_address(address);
+        self.missed.retain(|missed| *missed != address);
+        self.lost = self.missed.len() as i32;
+        self.to_post -= 1;
+    }
+
+    /// Another postman delivered the house first
+    pub fn lose_to_rival(&mut self, address: u32) {
+        self.remove_address(address);
+        self.missed.retain(|missed| *missed != address);
+        self.lost = self.missed.len() as i32;
+        self.taken += 1;
+    }
+
+    pub fn delivered(&self) -> i32 {
+        self.all - self.to_post
+    }
+
+    /// Houses not passed yet
+    pub fn pending(&self) -> i32 {
+        self.to_post - self.lost - self.taken
+    }
+}
+
+/// The house got its delivery from `postman`, it leaves the bags of the other ones
+pub fn hand_over<'a>(
+    postmen: impl Iterator<Item = (Entity, Mut<'a, Letters>)>,
+    postman: Entity,
+    address: u32,
+) {
+    for (entity, mut letters) in postmen {
+        if entity == postman {
+            letters.deliver(address);
+        } else {
+            letters.lose_to_rival(address);
+        }
+    }
+}
+
+/// State of the delivery of a house, set once the player passed its launch zone
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum Delivery {
+    #[default]
+    Pending,
+    Delivered,
+    Missed,
 }
 
+/// House number, letters are addressed to it
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct Address(pub u32);
+
+/// What a house expects
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum DeliveryKind {
+    #[default]
+    Letter,
+    /// Handed over at the door, the player has to stop
+    Parcel,
+}
+
+/// Shown over the letter box of a missed house
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct MissedMarker;
+
+const MISSED_MARKER_SIZE: f32 = 6.;
+
 // Link the launch zone to letter box
 #[derive(Component, Reflect, Debug)]
 #[reflect(Component)]
 pub struct LetterLaunchZone(pub Entity);
 
+/// Mirror a launch zone along the road, around the house chunk center
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct LaunchZoneMirror {
+    pub pivot: Vec2,
+    /// Direction of the road, `Vec2::X` or `Vec2::Y`
+    pub axis: Vec2,
+}
+
+impl LaunchZoneMirror {
+    pub fn mirror(&self, position: Vec2) -> Vec2 {
+        position + self.axis * 2. * (self.pivot - position).dot(self.axis)
+    }
+}
+
 #[derive(Component, Reflect, Debug)]
 #[reflect(Component)]
 pub struct LetterBox;
 
+/// A thrown letter, flying over the ground
 #[derive(Component, Reflect, Debug)]
 #[reflect(Component)]
-pub struct Letter(pub Vec3);
+pub struct Letter {
+    /// Ground speed, in px/s
+    pub velocity: Vec2,
+    pub height: f32,
+    pub vertical_speed: f32,
+    /// Letter box the letter was thrown at
+    pub target: Entity,
+    /// Postman who threw it
+    pub postman: Entity,
+    /// House the letter is addressed to
+    pub address: u32,
+}
+
+/// A letter fallen in the grass
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct LostLetter(pub Timer);
+
+/// Walls of a house, letters bounce off them
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct HouseWall;
+
+const THROW_SPEED: f32 = 110.;
+const THROW_VERTICAL_SPEED: f32 = 90.;
+const LETTER_GRAVITY: f32 = 360.;
+const LETTER_DRAG: f32 = 1.2;
+const LETTER_SCALE: f32 = 0.5;
+/// A letter lower than this can enter a letter box
+const LETTER_BOX_HEIGHT: f32 = 8.;
+const WALL_BOUNCE: f32 = 0.4;
+const NEXT_ADDRESS_COLOR: Color = Color::srgb(1., 0.85, 0.3);
 
 #[derive(Component, Reflect, Debug)]
 #[reflect(Component)]
@@ -87,32 +274,61 @@ pub struct LetterUi {
 
 pub fn update_letter_ui(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut LetterUi)>,
-    letters: Res<Letters>,
+    mut query: Query<(Entity, &mut LetterUi, &HudOf)>,
+    letters_query: Query<&Letters>,
     aseprites: Res<AsepriteAssets>,
+    fonts: Res<FontAssets>,
 ) {
-    if let Ok((entity, mut letter_ui)) = query.get_single_mut() {
+    for (entity, mut letter_ui, hud) in query.iter_mut() {
+        let Ok(letters) = letters_query.get(hud.0) else {
+            continue;
+        };
+
         if letter_ui.show != *letters {
             println!("Update letter ui");
             commands.entity(entity).despawn_descendants();
 
+            // the letters in the bag, the next address first
             commands.entity(entity).with_children(|children| {
-                for i in 0..letters.to_post {
-                    children.spawn((
-                        NodeBundle {
+                for (i, address) in letters.queue.iter().enumerate() {
+                    children
+                        .spawn(NodeBundle {
                             style: Style {
-                                width: Px(22. * 2.),
-                                height: Px(16. * 2.),
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
                                 ..default()
                             },
                             ..default()
-                        },
-                        AsepriteAnimationUiBundle {
-                            aseprite: aseprites.get("letter"),
-                            animation: Animation::default().with_tag("default"),
-                            ..default()
-                        },
-                    ));
+                        })
+                        .with_children(|children| {
+                            children.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        width: Px(22. * 2.),
+                                        height: Px(16. * 2.),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                AsepriteAnimationUiBundle {
+                                    aseprite: aseprites.get("letter"),
+                                    animation: Animation::default().with_tag("default"),
+                                    ..default()
+                                },
+                            ));
+                            children.spawn(TextBundle::from_section(
+                                address.to_string(),
+                                TextStyle {
+                                    font_size: if i == 0 { 24. } else { 16. },
+                                    color: if i == 0 {
+                                        NEXT_ADDRESS_COLOR
+                                    } else {
+                                        Color::WHITE
+                                    },
+                                    font: fonts.get("gamer"),
+                                },
+                            ));
+                        });
                 }
             });
 
@@ -123,11 +339,15 @@ pub fn update_letter_ui(
 
 pub fn launch_zone_detection(
     mut commands: Commands,
-    mut player_query: Query<(&Collider, &mut PlayerController), With<Player>>,
+    mut player_query: Query<
+        (&Collider, &mut PlayerController, &mut Letters, Has<Rival>),
+        With<Player>,
+    >,
     launch_zone_query: Query<(&Collider, &LetterLaunchZone), Without<Player>>,
+    mut delivery_query: Query<(&mut Delivery, &DeliveryKind, &Address)>,
     mut info_text: ResMut<InfoText>,
 ) {
-    if let Ok((player_collider, mut controller)) = player_query.get_single_mut() {
+    for (player_collider, mut controller, mut letters, rival) in player_query.iter_mut() {
         for (zone_collider, zone) in launch_zone_query.iter() {
             // Enter a launch zone
             if player_collider.collide(zone_collider) {
@@ -138,37 +358,64 @@ pub fn launch_zone_detection(
                     controller.can_launch_letter = true;
                     controller.closest_launch_zone = Some(zone_collider.clone());
                     controller.letter_target = Some(zone.0);
-                    info_text.set("Press SPACE to launch a letter");
+
+                    match delivery_query.get(zone.0).map(|(_, kind, _)| *kind) {
+                        _ if rival => {}
+                        Ok(DeliveryKind::Parcel) => {
+                            info_text.set("Stop and hold E to drop the parcel")
+                        }
+                        _ => info_text.set("Press SPACE to launch the next letter"),
+                    }
                 }
             }
 
             // Quit the launch zone
             if let Some(collider) = &controller.closest_launch_zone {
                 if !player_collider.collide(collider) {
+                    // rode past the house without throwing
+                    if !controller.letter_launched {
+                        if let Some((mut delivery, _, address)) = controller
+                            .letter_target
+                            .and_then(|target| delivery_query.get_mut(target).ok())
+                        {
+                            if *delivery != Delivery::Delivered {
+                                letters.miss(address.0);
+                            }
+                            if *delivery == Delivery::Pending {
+                                *delivery = Delivery::Missed;
+                            }
+                        }
+                    }
+
                     controller.can_launch_letter = false;
                     controller.closest_launch_zone = None;
                     controller.letter_target = None;
                     controller.letter_launched = false;
 
                     commands.entity(zone.0).remove::<LetterTarget>();
-                    info_text.reset();
+                    if !rival {
+                        info_text.reset();
+                    }
                 }
             }
         }
     }
 }
 
+/// The flag of a closed letter box stays raised until its mail is collected
 pub fn animate_letter_box(
-    mut query: Query<(&mut Animation, Option<&LetterTarget>), With<LetterBox>>,
+    mut query: Query<(&mut Animation, Option<&LetterTarget>, Has<OutgoingMail>), With<LetterBox>>,
 ) {
-    for (mut animation, focus) in query.iter_mut() {
+    for (mut animation, focus, mail) in query.iter_mut() {
         if let Some(_) = focus {
             if animation.tag != Some("letter-enter".into()) {
                 animation.play("focus", AnimationRepeat::Loop);
             }
         } else {
-            if animation.tag == Some("focus".into()) {
-                animation.play("close", AnimationRepeat::Loop);
+            let closed = if mail { "flag" } else { "close" };
+            if animation.tag != Some(closed.into()) && animation.tag != Some("letter-enter".into())
+            {
+                animation.play(closed, AnimationRepeat::Loop);
             }
         }
     }
@@ -176,37 +423,70 @@ pub fn animate_letter_box(
 
 pub fn launch_letter(
     mut commands: Commands,
-    keys: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<(&Transform, &mut PlayerController, &mut Animation), With<Player>>,
-    target_query: Query<&GlobalTransform, (With<LetterTarget>, Without<Letter>)>,
+    fixed_time: Res<Time<Fixed>>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Velocity,
+            &mut PlayerController,
+            &mut Animation,
+            &PlayerInput,
+            &Letters,
+        ),
+        With<Player>,
+    >,
+    target_query: Query<(&GlobalTransform, &DeliveryKind), (With<LetterBox>, Without<Letter>)>,
     aseprites: Res<AsepriteAssets>,
 ) {
-    if let Ok((transform, mut controller, mut animation)) = player_query.get_single_mut() {
-        if let Ok(target_transform) = target_query.get_single() {
-            if keys.pressed(KeyCode::Space)
-                && controller.can_launch_letter
-                && !controller.letter_launched
-            {
+    for (postman, transform, velocity, mut controller, mut animation, input, letters) in
+        player_query.iter_mut()
+    {
+        let Some(target) = controller.letter_target else {
+            continue;
+        };
+        if let Ok((target_transform, kind)) = target_query.get(target) {
+            // parcels are handed over at the door
+            if *kind == DeliveryKind::Parcel {
+                continue;
+            }
+
+            // the next letter of the bag is thrown, whatever house it is for
+            let Some(address) = letters.next() else {
+                continue;
+            };
+
+            if input.launch && controller.can_launch_letter && !controller.letter_launched {
                 commands.trigger(PlaySfx::Key("launch".into()));
 
                 // Animate player
                 animation.play("launch-letter", AnimationRepeat::Count(0));
                 animation.then("ride", AnimationRepeat::Loop);
 
+                // aimed at the letter box, the letter keeps the speed of the bike
+                let position = transform.translation.xy() + Vec2::X * -8.;
+                let aim = (target_transform.translation().xy() - position).normalize_or_zero();
+                let bike_velocity = velocity.0 / fixed_time.timestep().as_secs_f32();
+
                 commands.spawn((
                     Name::new("Letter"),
                     StateScoped(Screen::Playing),
                     AsepriteAnimationBundle {
                         aseprite: aseprites.get("letter"),
                         animation: Animation::default().with_tag("default"),
-                        transform: Transform::from_translation(
-                            transform.translation.xy().extend(0.5) + Vec3::X * -8.,
-                        )
-                        .with_scale(Vec2::splat(0.5).extend(0.)),
+                        transform: Transform::from_translation(position.extend(0.5))
+                            .with_scale(Vec2::splat(LETTER_SCALE).extend(0.)),
                         ..default()
                     },
                     Collider::rect(4., 4.),
-                    Letter(target_transform.translation()),
+                    Letter {
+                        velocity: bike_velocity + aim * THROW_SPEED,
+                        height: 0.,
+                        vertical_speed: THROW_VERTICAL_SPEED,
+                        target,
+                        postman,
+                        address,
+                    },
                 ));
 
                 controller.letter_launched = true;
@@ -215,48 +495,219 @@ pub fn launch_letter(
     }
 }
 
-pub fn move_letter(time: Res<Time>, mut letter_query: Query<(&mut Transform, &Letter)>) {
-    if let Ok((mut letter_transfrom, letter)) = letter_query.get_single_mut() {
-        let target_translation = letter.0;
-        letter_transfrom.translation = letter_transfrom
-            .translation
-            .lerp(target_translation, time.delta_seconds() * 4.);
-        letter_transfrom.rotation = Quat::from_rotation_z(
-            (target_translation - letter_transfrom.translation)
-                .xy()
-                .to_angle(),
-        );
+pub fn fly_letters(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut letter_query: Query<(Entity, &mut Transform, &mut Letter)>,
+    wall_query: Query<&Collider, With<HouseWall>>,
+    mut delivery_query: Query<(&mut Delivery, &Address)>,
+    mut letters_query: Query<&mut Letters>,
+) {
+    let delta = time.delta_seconds();
+
+    for (entity, mut transform, mut letter) in letter_query.iter_mut() {
+        letter.velocity *= 1. - (LETTER_DRAG * delta).min(1.);
+        letter.vertical_speed -= LETTER_GRAVITY * delta;
+        letter.height += letter.vertical_speed * delta;
+
+        let position = transform.translation.xy() + letter.velocity * delta;
+        let letter_collider = Collider::new_rect(position, Vec2::splat(4.));
+
+        // bounce off the walls it is flying into
+        for wall in wall_query.iter() {
+            if letter_collider.collide(wall) && letter.velocity.dot(wall.center() - position) > 0. {
+                letter.velocity = -letter.velocity * WALL_BOUNCE;
+            }
+        }
+
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotate_z(8. * delta);
+        transform.scale = Vec2::splat(LETTER_SCALE * (1. + letter.height.max(0.) / 30.)).extend(0.);
+
+        // fallen in the grass, the house it was addressed to won't get it
+        // and neither will the one it was thrown at
+        if letter.height <= 0. {
+            if let Ok(mut letters) = letters_query.get_mut(letter.postman) {
+                letters.remove_address(letter.address);
+
+                let target = delivery_query
+                    .get(letter.target)
+                    .ok()
+                    .map(|(_, address)| address.0);
+                for (mut delivery, address) in delivery_query.iter_mut() {
+                    if address.0 != letter.address && Some(address.0) != target {
+                        continue;
+                    }
+                    if *delivery != Delivery::Delivered {
+                        letters.miss(address.0);
+                    }
+                    if *delivery == Delivery::Pending {
+                        *delivery = Delivery::Missed;
+                    }
+                }
+            }
+
+            commands
+                .entity(entity)
+                .remove::<(Letter, Collider)>()
+                .insert(LostLetter(Timer::from_seconds(2., TimerMode::Once)));
+        }
+    }
+}
+
+fn fade_lost_letters(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut LostLetter)>,
+) {
+    for (entity, mut sprite, mut lost) in query.iter_mut() {
+        lost.0.tick(time.delta());
+
+        if lost.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            sprite.color.set_alpha(lost.0.fraction_remaining());
+        }
     }
 }
 
 fn remove_letter(
     mut commands: Commands,
-    letter_query: Query<(Entity, &Collider), With<Letter>>,
+    letter_query: Query<(Entity, &Collider, &Letter)>,
     mut target_query: Query<
-        (Entity, &Collider, &mut Animation),
+        (
+            Entity,
+            &Collider,
+            &mut Animation,
+            &mut Delivery,
+            &DeliveryKind,
+            &Address,
+        ),
         (With<LetterBox>, Without<Letter>),
     >,
-    mut letters: ResMut<Letters>,
+    mut letters_query: Query<(Entity, &mut Letters), With<Player>>,
+    mut info_text: ResMut<InfoText>,
 ) {
-    if let Ok((letter, letter_collider)) = letter_query.get_single() {
-        for (_, target_collider, mut target_animation) in target_query.iter_mut() {
-            if letter_collider.collide(target_collider) {
+    if !letter_query.is_empty() {
+        for (letter, letter_collider, flight) in letter_query.iter() {
+            if flight.height > LETTER_BOX_HEIGHT {
+                continue;
+            }
+
+            let mut wrong_address = None;
+
+            for (_, target_collider, mut target_animation, mut delivery, kind, address) in
+                target_query.iter_mut()
+            {
+                // parcels are handed over at the door, not through the letter box
+                if *kind == DeliveryKind::Parcel || !letter_collider.collide(target_collider) {
+                    continue;
+                }
+
                 // Remove the letter and play letter box animation
                 target_animation.play("letter-enter", AnimationRepeat::Count(0));
                 target_animation.then("close", AnimationRepeat::Loop);
 
                 commands.entity(letter).despawn();
+                let Ok((_, mut letters)) = letters_query.get_mut(flight.postman) else {
+                    break;
+                };
+                letters.remove_address(flight.address);
 
-                // Update letters
-                letters.to_post -= 1;
+                // a wrong letter box keeps the letter, its own is missed too
+                if address.0 != flight.address {
+                    if *delivery != Delivery::Delivered {
+                        letters.miss(address.0);
+                    }
+                    if *delivery == Delivery::Pending {
+                        *delivery = Delivery::Missed;
+                    }
+                    letters.wrong += 1;
+                    info_text.set("Wrong address!");
+                    wrong_address = Some(flight.address);
+                    break;
+                }
+
+                // Update letters, a house only takes one letter
+                if *delivery != Delivery::Delivered {
+                    *delivery = Delivery::Delivered;
+                    hand_over(letters_query.iter_mut(), flight.postman, address.0);
+                }
+                break;
+            }
+
+            // the house it was for won't get its letter
+            if let Some(missed) = wrong_address {
+                for (_, _, _, mut delivery, _, address) in target_query.iter_mut() {
+                    if address.0 != missed || *delivery == Delivery::Delivered {
+                        continue;
+                    }
+
+                    if let Ok((_, mut letters)) = letters_query.get_mut(flight.postman) {
+                        letters.miss(missed);
+                    }
+                    if *delivery == Delivery::Pending {
+                        *delivery = Delivery::Missed;
+                    }
+                }
             }
         }
     } else {
         // TODO mdr si j'ai le temps
         if target_query.iter().count() > 1 {
-            for (entity, _, _) in target_query.iter() {
+            for (entity, ..) in target_query.iter() {
                 commands.entity(entity).remove::<LetterTarget>();
             }
         }
     }
 }
+
+fn mark_missed_houses(
+    mut commands: Commands,
+    query: Query<(Entity, &Delivery, Option<&Children>), Changed<Delivery>>,
+    marker_query: Query<Entity, With<MissedMarker>>,
+) {
+    for (entity, delivery, children) in query.iter() {
+        let markers = children
+            .into_iter()
+            .flatten()
+            .filter(|child| marker_query.contains(**child));
+
+        if *delivery == Delivery::Missed {
+            commands.entity(entity).with_children(|children| {
+                children.spawn((
+                    Name::new("Missed Marker"),
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::Srgba(RED),
+                            custom_size: Some(Vec2::splat(MISSED_MARKER_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0., 12., 0.1)
+                            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                        ..default()
+                    },
+                    MissedMarker,
+                ));
+            });
+        } else {
+            for marker in markers {
+                commands.entity(*marker).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Houses are numbered clockwise, the queue is reversed for the other way
+fn order_letters(mut query: Query<(&Circuit, &mut Letters)>) {
+    for (circuit, mut letters) in query.iter_mut() {
+        if !circuit.direction_chosen || letters.ordered {
+            continue;
+        }
+
+        if circuit.direction == CircuitDirection::AntiClockwise {
+            letters.queue.reverse();
+        }
+        letters.ordered = true;
+    }
+}
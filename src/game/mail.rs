@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::screen::Screen;
+
+use super::{
+    audio::sfx::PlaySfx,
+    collider::Collider,
+    input::PlayerInput,
+    letter::{LetterBox, Letters},
+    map::chunk::PIXEL_CHUNK_SIZE,
+    spawn::{
+        map::PostOffice,
+        player::{Player, PlayerController},
+    },
+    ui::InfoText,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<OutgoingMail>();
+    app.add_systems(
+        FixedUpdate,
+        (collect_mail, return_mail)
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// A letter box with mail to bring back to the post office, its flag is raised
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct OutgoingMail;
+
+/// Distance to a letter box to grab its mail
+const COLLECT_DISTANCE: f32 = 28.;
+/// Distance to the post office to hand the mail over, the start is next to it
+const RETURN_DISTANCE: f32 = PIXEL_CHUNK_SIZE * 1.5;
+
+fn collect_mail(
+    mut commands: Commands,
+    mut player_query: Query<
+        (&Transform, &PlayerController, &PlayerInput, &mut Letters),
+        With<Player>,
+    >,
+    letter_box_query: Query<(Entity, &GlobalTransform), (With<LetterBox>, With<OutgoingMail>)>,
+    mut info_text: ResMut<InfoText>,
+) {
+    // a letter box only holds mail for the first postman to stop by
+    let mut collected = Vec::new();
+
+    for (transform, controller, input, mut letters) in player_query.iter_mut() {
+        if !input.collect || controller.damn {
+            continue;
+        }
+
+        let position = transform.translation.xy();
+        for (entity, letter_box_transform) in letter_box_query.iter() {
+            if collected.contains(&entity)
+                || position.distance(letter_box_transform.translation().xy()) > COLLECT_DISTANCE
+            {
+                continue;
+            }
+
+            commands.trigger(PlaySfx::Key("launch".into()));
+            commands.entity(entity).remove::<OutgoingMail>();
+
+            collected.push(entity);
+            letters.mail_carried += 1;
+            info_text.set("Mail collected, bring it back to the post office");
+        }
+    }
+}
+
+fn return_mail(
+    mut player_query: Query<(&Transform, &mut Letters), With<Player>>,
+    post_office_query: Query<&Collider, With<PostOffice>>,
+    mut info_text: ResMut<InfoText>,
+) {
+    for (transform, mut letters) in player_query.iter_mut() {
+        if letters.mail_carried == 0 {
+            continue;
+        }
+
+        let position = transform.translation.xy();
+        if post_office_query
+            .iter()
+            .any(|collider| position.distance(collider.center()) < RETURN_DISTANCE)
+        {
+            letters.mail_returned += letters.mail_carried;
+            letters.mail_carried = 0;
+            info_text.set("Mail handed over!");
+        }
+    }
+}
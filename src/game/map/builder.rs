@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 
 use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
 
 use crate::game::map::types::ObstacleType;
+use crate::game::score::ScoreModel;
 
 use super::{
     chunk::{
@@ -11,11 +13,17 @@ use super::{
         PIXEL_CHUNK_SIZE,
     },
     ldtk::Project,
+    road::RoadNetwork,
     transformer::generate_level,
     types::IntgridType,
     utils::inline_csv_to_matrix,
 };
 
+/// Number of road chunks between two checkpoints
+pub const CHECKPOINT_INTERVAL: usize = 5;
+/// Road chunks on each side of the start left without a shuffled obstacle
+const START_CLEARANCE: usize = 2;
+
 pub struct MapBuilder {
     maps: Project,
     /// list of chunks which can be placed in the map
@@ -104,6 +112,21 @@ impl MapBuilder {
 
         println!("Map size: {}x{}", map.tile_x(), map.tile_y());
 
+        self.map.traffic_density = map.get_f32_field("TrafficDensity").unwrap_or_default();
+        self.map.laps = map.get_f32_field("Laps").unwrap_or(1.).max(1.) as usize;
+        self.map.parcel_ratio = map.get_f32_field("ParcelRatio").unwrap_or_default();
+        self.map.mail_ratio = map.get_f32_field("MailRatio").unwrap_or_default();
+
+        if let Some(par) = map.get_f32_field("Par") {
+            self.map.score_model.par = par;
+        }
+        let star_fields = ["OneStarScore", "TwoStarScore", "ThreeStarScore"];
+        for (threshold, field) in self.map.score_model.star_scores.iter_mut().zip(star_fields) {
+            if let Some(score) = map.get_f32_field(field) {
+                *threshold = score as i32;
+            }
+        }
+
         // Tiles
         let tiles = generate_level(
             inline_csv_to_matrix(base.clone(), map.tile_y(), map.tile_x()),
@@ -204,11 +227,68 @@ impl MapBuilder {
                 self.map.decor_chunks.push(chunk);
             }
         }
+
+        // Track along the loop, from the chunk the player starts on
+        let start = RoadNetwork::cell_at(self.map.start_position);
+        self.map.track = RoadNetwork::from_map(&self.map).track(start);
+        self.map.checkpoints = (CHECKPOINT_INTERVAL..self.map.track.len())
+            .step_by(CHECKPOINT_INTERVAL)
+            .collect();
+    }
+
+    /// Reroll the decor and move the obstacles to other road chunks, for a seeded route
+    pub fn shuffle(&mut self, rng: &mut impl Rng) {
+        for chunk in self.map.decor_chunks.iter_mut() {
+            if !matches!(chunk.chunk_type, ChunkType::Decor(_)) {
+                continue;
+            }
+
+            let decor = ChunkType::Decor(rng.gen_range(1..=4));
+            if let Some(template) = self.chunks.get(&decor) {
+                let position = chunk.position;
+                *chunk = template.clone();
+                chunk.position = position;
+            }
+        }
+
+        // obstacles keep their kind, the chunks around the start stay clear
+        let len = self.map.track.len();
+        let cells: Vec<IVec2> = self
+            .map
+            .track
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i >= START_CLEARANCE && *i + START_CLEARANCE <= len)
+            .map(|(_, cell)| *cell)
+            .collect();
+        let picked: Vec<IVec2> = cells
+            .choose_multiple(rng, self.map.obstacles.len())
+            .copied()
+            .collect();
+        self.map.obstacles.truncate(picked.len());
+
+        for (obstacle, cell) in self.map.obstacles.iter_mut().zip(picked) {
+            let Some(chunk) =
+                self.map.chunks.iter().find(|chunk| {
+                    !chunk.is_empty() && RoadNetwork::cell_of(chunk.position) == cell
+                })
+            else {
+                continue;
+            };
+
+            obstacle.chunk = chunk.clone();
+            obstacle.chunk_center = RoadNetwork::center(cell);
+        }
     }
 
     pub fn get_map(&self) -> Map {
         self.map.clone()
     }
+
+    /// Chunks of `chunks.ldtk`, by type
+    pub fn templates(&self) -> HashMap<ChunkType, Chunk> {
+        self.chunks.clone()
+    }
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
@@ -219,6 +299,18 @@ pub struct Map {
     pub decor_chunks: Vec<Chunk>,
     pub start_position: Vec2,
     pub obstacles: Vec<Obstacle>,
+    /// Cars per road chunk
+    pub traffic_density: f32,
+    /// Road chunks of the loop in clockwise order, starting from the chunk at the start position
+    pub track: Vec<IVec2>,
+    /// Indices in `track` of the checkpoints
+    pub checkpoints: Vec<usize>,
+    pub laps: usize,
+    /// Share of the houses waiting for a parcel
+    pub parcel_ratio: f32,
+    /// Share of the letter boxes holding outgoing mail
+    pub mail_ratio: f32,
+    pub score_model: ScoreModel,
     // interactables: Vec<Interactable>,
 }
 
@@ -111,7 +111,7 @@ pub enum RoadChunkType {
     Turn,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Reflect)]
 pub enum ChunkConnextion {
     Top,
     Right,
@@ -119,6 +119,53 @@ pub enum ChunkConnextion {
     Left,
 }
 
+impl ChunkConnextion {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Top => Self::Bottom,
+            Self::Right => Self::Left,
+            Self::Bottom => Self::Top,
+            Self::Left => Self::Right,
+        }
+    }
+
+    /// Offset in the map grid, y goes down like in LDtk
+    pub fn grid_offset(&self) -> IVec2 {
+        match self {
+            Self::Top => IVec2::new(0, -1),
+            Self::Right => IVec2::new(1, 0),
+            Self::Bottom => IVec2::new(0, 1),
+            Self::Left => IVec2::new(-1, 0),
+        }
+    }
+
+    pub fn from_grid_offset(offset: IVec2) -> Option<Self> {
+        match (offset.x, offset.y) {
+            (0, -1) => Some(Self::Top),
+            (1, 0) => Some(Self::Right),
+            (0, 1) => Some(Self::Bottom),
+            (-1, 0) => Some(Self::Left),
+            _ => None,
+        }
+    }
+
+    /// Direction in the world, y goes up
+    pub fn to_vec2(self) -> Vec2 {
+        let offset = self.grid_offset();
+        Vec2::new(offset.x as f32, -offset.y as f32)
+    }
+
+    /// Rotation around z of something facing this way, 0 is facing up
+    pub fn angle(&self) -> f32 {
+        match self {
+            Self::Top => 0.,
+            Self::Left => std::f32::consts::PI / 2.,
+            Self::Bottom => std::f32::consts::PI,
+            Self::Right => -std::f32::consts::PI / 2.,
+        }
+    }
+}
+
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct House {
     pub position: Vec2,
@@ -43,6 +43,21 @@ impl Level {
             .unwrap()
     }
 
+    pub fn find_field(&self, field_name: &str) -> Option<&FieldInstance> {
+        self.field_instances
+            .iter()
+            .find(|field| field.identifier == field_name)
+    }
+
+    /// Value of a Float or Int field, `None` if the level doesn't have it
+    pub fn get_f32_field(&self, field_name: &str) -> Option<f32> {
+        self.find_field(field_name)?
+            .value
+            .as_ref()?
+            .as_f64()
+            .map(|value| value as f32)
+    }
+
     #[inline]
     pub fn tile_x(&self) -> i64 {
         self.px_wid / 16
@@ -2,6 +2,7 @@ mod autotile;
 pub mod builder;
 pub mod chunk;
 pub mod ldtk;
+pub mod road;
 mod transformer;
 pub mod types;
 mod utils;
@@ -0,0 +1,181 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{
+    builder::Map,
+    chunk::{ChunkConnextion, ChunkType, PIXEL_CHUNK_SIZE},
+};
+
+/// Road chunks of the map and how they are linked together
+#[derive(Resource, Default, Clone, Debug)]
+pub struct RoadNetwork {
+    pub nodes: HashMap<IVec2, Vec<ChunkConnextion>>,
+}
+
+impl RoadNetwork {
+    pub fn from_map(map: &Map) -> Self {
+        let mut nodes = HashMap::new();
+        for chunk in map.chunks.iter() {
+            if matches!(chunk.chunk_type, ChunkType::Road(_)) {
+                nodes.insert(Self::cell_of(chunk.position), chunk.connextions.clone());
+            }
+        }
+
+        // only keep connexions that lead to another road chunk
+        let cells: Vec<IVec2> = nodes.keys().copied().collect();
+        for cell in cells {
+            let connexions = nodes[&cell]
+                .iter()
+                .filter(|connexion| nodes.contains_key(&(cell + connexion.grid_offset())))
+                .copied()
+                .collect();
+            nodes.insert(cell, connexions);
+        }
+
+        Self { nodes }
+    }
+
+    /// Grid cell of a chunk from its position
+    pub fn cell_of(chunk_position: Vec2) -> IVec2 {
+        IVec2::new(
+            (chunk_position.x / PIXEL_CHUNK_SIZE).round() as i32,
+            (-chunk_position.y / PIXEL_CHUNK_SIZE).round() as i32,
+        )
+    }
+
+    /// Grid cell under a world position
+    pub fn cell_at(position: Vec2) -> IVec2 {
+        IVec2::new(
+            ((position.x + 8.) / PIXEL_CHUNK_SIZE).floor() as i32,
+            ((-position.y + 8.) / PIXEL_CHUNK_SIZE).floor() as i32,
+        )
+    }
+
+    /// World position of the center of a cell
+    pub fn center(cell: IVec2) -> Vec2 {
+        Vec2::new(
+            PIXEL_CHUNK_SIZE * cell.x as f32 + PIXEL_CHUNK_SIZE / 2. - 8.,
+            -PIXEL_CHUNK_SIZE * cell.y as f32 - PIXEL_CHUNK_SIZE / 2. + 8.,
+        )
+    }
+
+    pub fn connexions(&self, cell: IVec2) -> &[ChunkConnextion] {
+        self.nodes.get(&cell).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    /// Ways out of a cell when coming with `heading`, without turning back
+    pub fn exits(&self, cell: IVec2, heading: ChunkConnextion) -> Vec<ChunkConnextion> {
+        self.connexions(cell)
+            .iter()
+            .filter(|connexion| **connexion != heading.opposite())
+            .copied()
+            .collect()
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &IVec2> {
+        self.nodes.keys()
+    }
+
+    /// Road chunks of the loop going through `start`, in clockwise order.
+    /// The walk goes straight at junctions and stops on a dead end.
+    pub fn track(&self, start: IVec2) -> Vec<IVec2> {
+        let Some(mut heading) = self.connexions(start).first().copied() else {
+            return Vec::new();
+        };
+
+        let mut track = vec![start];
+        let mut cell = start;
+        loop {
+            cell += heading.grid_offset();
+            if cell == start || track.len() > self.nodes.len() {
+                break;
+            }
+            track.push(cell);
+
+            let exits = self.exits(cell, heading);
+            heading = if exits.contains(&heading) {
+                heading
+            } else if let Some(exit) = exits.first() {
+                *exit
+            } else {
+                break;
+            };
+        }
+
+        // shoelace formula, the grid y axis goes down
+        let area: i32 = track
+            .iter()
+            .zip(track.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * -b.y - b.x * -a.y)
+            .sum();
+        if area > 0 {
+            track[1..].reverse();
+        }
+
+        track
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Road loop going through `cells` in their order
+    fn ring(cells: &[IVec2]) -> RoadNetwork {
+        let mut nodes = HashMap::new();
+        for (i, cell) in cells.iter().enumerate() {
+            let next = cells[(i + 1) % cells.len()];
+            let previous = cells[(i + cells.len() - 1) % cells.len()];
+            nodes.insert(
+                *cell,
+                vec![
+                    ChunkConnextion::from_grid_offset(next - *cell).unwrap(),
+                    ChunkConnextion::from_grid_offset(previous - *cell).unwrap(),
+                ],
+            );
+        }
+        RoadNetwork { nodes }
+    }
+
+    /// Border of a 3x3 square, clockwise on screen from its top left corner
+    const CLOCKWISE: [IVec2; 8] = [
+        IVec2::new(0, 0),
+        IVec2::new(1, 0),
+        IVec2::new(2, 0),
+        IVec2::new(2, 1),
+        IVec2::new(2, 2),
+        IVec2::new(1, 2),
+        IVec2::new(0, 2),
+        IVec2::new(0, 1),
+    ];
+
+    #[test]
+    fn test_track_clockwise() {
+        let network = ring(&CLOCKWISE);
+        assert_eq!(network.track(IVec2::ZERO), CLOCKWISE.to_vec());
+    }
+
+    #[test]
+    fn test_track_walked_anticlockwise() {
+        let mut anticlockwise = CLOCKWISE.to_vec();
+        anticlockwise[1..].reverse();
+
+        let network = ring(&anticlockwise);
+        assert_eq!(network.track(IVec2::ZERO), CLOCKWISE.to_vec());
+    }
+
+    #[test]
+    fn test_track_starts_on_start() {
+        let network = ring(&CLOCKWISE);
+        let start = IVec2::new(2, 1);
+
+        let mut expected = CLOCKWISE.to_vec();
+        expected.rotate_left(3);
+        assert_eq!(network.track(start), expected);
+    }
+
+    #[test]
+    fn test_track_off_road() {
+        let network = ring(&CLOCKWISE);
+        assert!(network.track(IVec2::new(1, 1)).is_empty());
+    }
+}
@@ -40,6 +40,8 @@ impl From<&i64> for IntgridType {
 pub enum ObstacleType {
     WatterPuddle,
     RoadWork,
+    ManholeCover,
+    WorkCone,
     #[default]
     Empty,
 }
@@ -49,6 +51,8 @@ impl From<&i64> for ObstacleType {
         match value {
             1 => Self::WatterPuddle,
             2 => Self::RoadWork,
+            3 => Self::ManholeCover,
+            4 => Self::WorkCone,
             0 => Self::Empty,
             _ => Self::Empty,
         }
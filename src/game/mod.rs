@@ -6,14 +6,30 @@ pub mod audio;
 pub mod camera;
 pub mod circuit;
 pub mod collider;
+pub mod course;
+pub mod daily;
+pub mod endless;
+pub mod ghost;
 pub mod house;
+pub mod input;
+pub mod lan;
 pub mod letter;
-mod map;
+pub mod mail;
+pub mod map;
 pub mod movements;
+pub mod obstacle;
+pub mod parcel;
+pub mod replay;
 pub mod restart;
+pub mod rival;
+pub mod rng;
 pub mod save;
+pub mod score;
 pub mod spawn;
+pub mod touch;
+pub mod traffic;
 pub mod ui;
+pub mod versus;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<GameState>();
@@ -29,9 +45,25 @@ pub(super) fn plugin(app: &mut App) {
         movements::plugin,
         circuit::plugin,
         collider::plugin,
+        lan::plugin,
+        touch::plugin,
+    ));
+    app.add_plugins((
+        course::plugin,
+        daily::plugin,
+        endless::plugin,
+        ghost::plugin,
         house::plugin,
+        input::plugin,
         letter::plugin,
+        mail::plugin,
+        obstacle::plugin,
+        parcel::plugin,
+        replay::plugin,
         restart::plugin,
+        rival::plugin,
+        traffic::plugin,
+        versus::plugin,
     ));
 }
 
@@ -1,5 +1,3 @@
-use std::f32::consts::PI;
-
 use bevy::{math::VectorSpace, prelude::*};
 
 use crate::{screen::Screen, AppSet};
@@ -7,12 +5,12 @@ use crate::{screen::Screen, AppSet};
 use super::{
     circuit::{Circuit, CircuitDirection, EndCircuitTimer},
     collider::Collider,
+    input::PlayerInput,
     restart::RestartCooldown,
     spawn::{
         map::{ChunkTag, NotRoadTile},
         player::{Player, PlayerController, PlayerMovement},
     },
-    ui::InfoText,
     GameState,
 };
 
@@ -32,6 +30,8 @@ pub(super) fn plugin(app: &mut App) {
 }
 
 const PLAYER_FRICTION: f32 = 3.;
+const PLAYER_BRAKE_FRICTION: f32 = 12.;
+const PLAYER_DRIFT_FRICTION: f32 = 5.;
 const PLAYER_OFFROAD_FRICTION: f32 = 20.;
 
 #[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
@@ -41,94 +41,96 @@ pub struct Velocity(pub Vec2);
 pub fn player_movements(
     time: Res<Time>,
     end_timer: Res<EndCircuitTimer>,
-    keys: Res<ButtonInput<KeyCode>>,
     mut query: Query<
         (
             &mut Transform,
             &mut Velocity,
             &mut PlayerMovement,
             &mut PlayerController,
+            &PlayerInput,
+            &mut Circuit,
         ),
         With<Player>,
     >,
-    mut circuit: ResMut<Circuit>,
     restart_timer: Res<RestartCooldown>,
 ) {
-    if !restart_timer.0.finished() {
+    if !restart_timer.0.finished() || end_timer.elapsed_secs() > 1. {
         return;
     }
 
-    if let Ok((mut transform, mut velocity, mut movement, controller)) = query.get_single_mut() {
-        if end_timer.elapsed_secs() > 1. {
-            return;
-        }
+    // players on the same map ride the same way, the houses face one direction
+    let chosen_direction = query
+        .iter()
+        .find_map(|(.., circuit)| circuit.direction_chosen.then_some(circuit.direction));
 
-        let mut rotation_factor = 0.;
+    for (mut transform, mut velocity, mut movement, controller, input, mut circuit) in
+        query.iter_mut()
+    {
+        // from -1 on the right to 1 on the left, a stick turns gradually
+        let rotation_factor = input.steering();
 
         if !(end_timer.elapsed_secs() > 0.) && !controller.damn {
             // vertical axis
-            if keys.pressed(KeyCode::KeyW) {
-                movement.factor = 1.;
-            } else {
-                movement.factor = 0.;
-            }
+            movement.factor = input.throttle();
         } else {
             movement.factor = 0.;
         }
 
-        // brake
-        if keys.pressed(KeyCode::KeyS) {
-            movement.friction = 12.;
-        } else if movement.friction == 12. {
-            movement.friction = PLAYER_FRICTION;
-        }
-
         // horizontal axis
-        if keys.pressed(KeyCode::KeyD) {
-            // right
-            rotation_factor -= 1.;
-            movement.direction.x = -0.5;
-        } else if keys.pressed(KeyCode::KeyA) {
-            // left
-            rotation_factor += 1.;
-            movement.direction.x = 0.5;
-        } else {
-            movement.direction.x = 0.;
-        }
-
-        if circuit.direction_chosen && !circuit.in_turn {
-            // reset player rotation to the circuit direction
-
-            let mut reset_angle = match circuit.direction {
-                CircuitDirection::AntiClockwise => PI,
-                CircuitDirection::Clockwise => 0.,
-            };
-
-            let turn = (circuit.turn_count) as f32 * PI / 2.;
-
-            reset_angle += match circuit.direction {
-                CircuitDirection::AntiClockwise => -turn,
-                CircuitDirection::Clockwise => -turn,
+        movement.direction.x = rotation_factor * 0.5;
+
+        // brake, or drift when braking in a turn at speed
+        let was_drifting = movement.drifting;
+        movement.drifting = input.brake
+            && rotation_factor != 0.
+            && velocity.length() > movement.drift_min_speed
+            && !controller.damn;
+
+        if input.brake {
+            movement.friction = if movement.drifting {
+                PLAYER_DRIFT_FRICTION
+            } else {
+                PLAYER_BRAKE_FRICTION
             };
+        } else if movement.friction == PLAYER_BRAKE_FRICTION
+            || movement.friction == PLAYER_DRIFT_FRICTION
+        {
+            movement.friction = PLAYER_FRICTION;
+        }
 
+        if circuit.direction_chosen && !circuit.in_turn && !movement.drifting {
+            // reset player rotation to the track heading
             transform.rotation = transform.rotation.lerp(
-                Quat::from_axis_angle(Vec3::Z, reset_angle),
+                Quat::from_axis_angle(Vec3::Z, circuit.expected_angle()),
                 time.delta_seconds() * 10.,
             );
         } else if !circuit.direction_chosen {
-            // set circuit direction when the player start
-            if rotation_factor > 0. {
-                circuit.direction = CircuitDirection::Clockwise;
-                circuit.direction_chosen = true;
-            } else if rotation_factor < 0. {
-                circuit.direction = CircuitDirection::Clockwise; // haha no AntiClockwise
-                circuit.direction_chosen = true;
+            if let Some(direction) = chosen_direction {
+                circuit.choose_direction(direction);
+            } else if rotation_factor != 0. {
+                // set the circuit direction when the player starts, the way the player turns
+                let forward = (transform.rotation * Vec3::Y).xy();
+                let clockwise_on_left = circuit
+                    .start_heading(CircuitDirection::Clockwise)
+                    .is_some_and(|heading| forward.perp_dot(heading.to_vec2()) > 0.);
+
+                if (rotation_factor > 0.) == clockwise_on_left {
+                    circuit.choose_direction(CircuitDirection::Clockwise);
+                } else {
+                    circuit.choose_direction(CircuitDirection::AntiClockwise);
+                }
             }
         }
 
         // rotate player
-        if movement.direction.y != 0. {
-            let speed = if circuit.in_turn { 6.5 } else { 5. };
+        if movement.direction.y != 0. || movement.drifting {
+            let speed = if movement.drifting {
+                movement.drift_turn_speed
+            } else if circuit.in_turn {
+                6.5
+            } else {
+                5.
+            };
 
             if rotation_factor == 0. {
             } else {
@@ -174,6 +176,24 @@ pub fn player_movements(
             velocity.0 + delta_friction
         };
 
+        // lateral slip, the tyres only partly hold the bike on its heading
+        let heading = movement_direction.xy();
+        let forward = heading * velocity.dot(heading);
+        let lateral = velocity.0 - forward;
+        let grip = if movement.drifting {
+            movement.drift_grip
+        } else {
+            movement.grip
+        };
+
+        velocity.0 = forward + lateral * (1. - (grip * time.delta_seconds()).min(1.));
+        movement.slip = lateral.length();
+
+        // leaving a drift sends the remaining speed where the bike faces
+        if was_drifting && !movement.drifting && forward.dot(heading) > 0. {
+            velocity.0 = heading * velocity.length();
+        }
+
         // if controller.dashing {
         //     // println!("dash ...");
         //     controller.dash_timer.tick(time.delta());
@@ -210,11 +230,10 @@ fn off_the_road(
     mut player_query: Query<(&mut PlayerMovement, &mut PlayerController, &Collider), With<Player>>,
     chunk_query: Query<(&Children, &Collider), With<ChunkTag>>,
     tile_query: Query<(&Parent, &Collider), With<NotRoadTile>>,
-    mut info_text: ResMut<InfoText>,
 ) {
-    if let Ok((mut movement, mut controller, player_collider)) = player_query.get_single_mut() {
+    for (mut movement, mut controller, player_collider) in player_query.iter_mut() {
         if !controller.start_timer.finished() {
-            return;
+            continue;
         }
         for (children, chunk_collider) in chunk_query.iter() {
             if player_collider.collide(chunk_collider) {
@@ -243,7 +262,6 @@ fn off_the_road(
 
         if let Some(collider) = &controller.actual_chunk {
             if !player_collider.collide(collider) {
-                info_text.set("Press R to restart");
                 movement.friction = PLAYER_OFFROAD_FRICTION;
                 controller.actual_chunk = None;
             }
@@ -255,7 +273,7 @@ fn clear_movement_end(
     end_timer: Res<EndCircuitTimer>,
     mut player_query: Query<(&mut Transform, &mut Velocity, &mut PlayerController), With<Player>>,
 ) {
-    if let Ok((mut transform, mut velocity, _)) = player_query.get_single_mut() {
+    for (mut transform, mut velocity, _) in player_query.iter_mut() {
         if end_timer.elapsed_secs() > 1. {
             velocity.0 = Vec2::splat(0.);
         }
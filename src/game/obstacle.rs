@@ -0,0 +1,268 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::{Animation, AnimationRepeat};
+
+use crate::screen::Screen;
+
+use super::{
+    collider::Collider,
+    course::RespawnAtCheckpoint,
+    input::InputSet,
+    map::types::ObstacleType,
+    movements::Velocity,
+    restart::Restart,
+    rival::Rival,
+    spawn::{
+        map::{ChunkTag, ObstacleTag},
+        player::{Health, Player, PlayerController},
+    },
+    ui::InfoText,
+    versus::VersusMode,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(Bounce, KnockedAway)>();
+    app.add_systems(
+        FixedUpdate,
+        (
+            obstacle_check.after(InputSet).before(bounce_player),
+            bounce_player,
+            slow_knocked_obstacles,
+        )
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+const MANHOLE_SPEED_MUL: f32 = 0.6;
+const CONE_SPEED_MUL: f32 = 0.85;
+const CONE_PUSH: f32 = 1.5;
+const KNOCKED_FRICTION: f32 = 4.;
+const HIT_SPEED_MUL: f32 = 0.2;
+const FALL_DURATION: f32 = 1.5;
+
+/// Health lost when hitting an obstacle, cars have no kind
+fn hit_damage(kind: Option<ObstacleType>) -> i32 {
+    match kind {
+        Some(ObstacleType::WatterPuddle) => 1,
+        Some(ObstacleType::RoadWork) => 2,
+        Some(_) => 1,
+        None => 3,
+    }
+}
+
+/// Bigger hits keep the postman on the ground longer
+fn stun_duration(damage: i32) -> f32 {
+    0.4 + 0.2 * damage as f32
+}
+
+#[derive(Component, Debug, Deref)]
+pub struct ObstacleKind(pub ObstacleType);
+
+/// The bike jumps when riding over a manhole cover
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Bounce {
+    pub timer: Timer,
+    pub base_scale: Vec3,
+}
+
+impl Bounce {
+    pub fn new(base_scale: Vec3) -> Self {
+        Self {
+            timer: Timer::from_seconds(0.35, TimerMode::Once),
+            base_scale,
+        }
+    }
+}
+
+/// A cone that was hit by the bike and slides away
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct KnockedAway {
+    pub spin: f32,
+}
+
+fn obstacle_check(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Collider,
+            &mut Velocity,
+            &mut Animation,
+            &mut PlayerController,
+            &mut Health,
+            Option<&Bounce>,
+            Has<Rival>,
+        ),
+        With<Player>,
+    >,
+    obstacles_query: Query<
+        (Entity, &Collider, Option<&ObstacleKind>),
+        (With<ObstacleTag>, Without<Player>, Without<ChunkTag>),
+    >,
+    mut info_text: ResMut<InfoText>,
+    versus: Option<Res<VersusMode>>,
+) {
+    for (
+        player_entity,
+        transform,
+        player_collider,
+        mut velocity,
+        mut animation,
+        mut controller,
+        mut health,
+        bounce,
+        rival,
+    ) in player_query.iter_mut()
+    {
+        controller.start_timer.tick(time.delta());
+        health.invincible_timer.tick(time.delta());
+
+        if !controller.damn && controller.start_timer.finished() {
+            for (obstacle_entity, obstacle_collider, kind) in obstacles_query.iter() {
+                if !player_collider.collide(obstacle_collider) {
+                    if controller.last_obstacle == Some(obstacle_entity) {
+                        controller.last_obstacle = None;
+                    }
+                    continue;
+                }
+
+                if controller.last_obstacle == Some(obstacle_entity) {
+                    continue;
+                }
+
+                match kind.map(|kind| kind.0) {
+                    Some(ObstacleType::ManholeCover) => {
+                        controller.last_obstacle = Some(obstacle_entity);
+                        velocity.0 *= MANHOLE_SPEED_MUL;
+
+                        if bounce.is_none() {
+                            commands
+                                .entity(player_entity)
+                                .insert(Bounce::new(transform.scale));
+                        }
+                    }
+                    Some(ObstacleType::WorkCone) => {
+                        let push = (obstacle_collider.center() - player_collider.center())
+                            .normalize_or_zero()
+                            + velocity.normalize_or_zero();
+                        let spin = if velocity.perp_dot(push) > 0. {
+                            PI
+                        } else {
+                            -PI
+                        };
+
+                        commands
+                            .entity(obstacle_entity)
+                            .remove::<ObstacleTag>()
+                            .insert((
+                                Velocity(push.normalize_or_zero() * velocity.length() * CONE_PUSH),
+                                KnockedAway { spin: spin * 4. },
+                            ));
+
+                        velocity.0 *= CONE_SPEED_MUL;
+                    }
+                    // puddles, road works and cars
+                    kind => {
+                        if !health.can_be_hurt() {
+                            continue;
+                        }
+
+                        let damage = hit_damage(kind);
+                        health.hit(damage);
+
+                        let stun = if health.is_dead() {
+                            FALL_DURATION
+                        } else {
+                            stun_duration(damage)
+                        };
+
+                        controller.damn = true;
+                        controller.last_obstacle = Some(obstacle_entity);
+                        controller.obstacle_timer = Timer::from_seconds(stun, TimerMode::Once);
+                        velocity.0 *= HIT_SPEED_MUL;
+
+                        animation.play("fall", AnimationRepeat::Count(0));
+                        animation.clear_queue();
+                        break;
+                    }
+                }
+            }
+        }
+
+        if controller.damn {
+            controller.obstacle_timer.tick(time.delta());
+
+            if health.is_dead() {
+                if !rival {
+                    info_text.set("Oh no! You've broken a leg!");
+                }
+                if controller.obstacle_timer.finished() {
+                    if versus.is_some() || rival {
+                        // the race goes on, back on the bike at the last checkpoint
+                        commands.trigger(RespawnAtCheckpoint(player_entity));
+                        controller.damn = false;
+                        health.current = health.max;
+                        health.invincible_timer.reset();
+                        animation.play("ride", AnimationRepeat::Loop);
+                    } else {
+                        commands.trigger(Restart);
+                    }
+                }
+            } else {
+                if !rival {
+                    info_text.set("Ouch!");
+                }
+                if controller.obstacle_timer.finished() {
+                    // back on the bike
+                    controller.damn = false;
+                    health.invincible_timer.reset();
+                    animation.play("ride", AnimationRepeat::Loop);
+                    if !rival {
+                        info_text.reset();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn bounce_player(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Bounce), With<Player>>,
+) {
+    for (entity, mut transform, mut bounce) in query.iter_mut() {
+        bounce.timer.tick(time.delta());
+
+        if bounce.timer.finished() {
+            transform.scale = bounce.base_scale;
+            commands.entity(entity).remove::<Bounce>();
+        } else {
+            let height = (bounce.timer.fraction() * PI).sin();
+            transform.scale = bounce.base_scale * (1. + height * 0.3);
+        }
+    }
+}
+
+fn slow_knocked_obstacles(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Velocity, &mut KnockedAway)>,
+) {
+    for (entity, mut transform, mut velocity, mut knocked) in query.iter_mut() {
+        transform.rotate_z(knocked.spin * time.delta_seconds());
+
+        let friction = velocity.0 * -KNOCKED_FRICTION * time.delta_seconds();
+        velocity.0 += friction;
+        knocked.spin *= 1. - (KNOCKED_FRICTION * time.delta_seconds()).min(1.);
+
+        if velocity.length() < 0.05 {
+            commands.entity(entity).remove::<(Velocity, KnockedAway)>();
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::{Animation, AnimationRepeat};
+
+use crate::screen::Screen;
+
+use super::{
+    audio::sfx::PlaySfx,
+    input::PlayerInput,
+    letter::{hand_over, Address, Delivery, DeliveryKind, LetterBox, Letters},
+    movements::Velocity,
+    rival::Rival,
+    spawn::player::{Player, PlayerController},
+    ui::InfoText,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        deliver_parcel.run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Speed under which the postman can hand over a parcel, in px/tick
+const PARCEL_MAX_SPEED: f32 = 0.15;
+pub const PARCEL_COLOR: Color = Color::srgb(0.6, 0.4, 0.2);
+
+fn deliver_parcel(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            Entity,
+            &Velocity,
+            &mut PlayerController,
+            &PlayerInput,
+            Has<Rival>,
+        ),
+        With<Player>,
+    >,
+    mut letter_box_query: Query<
+        (&DeliveryKind, &mut Delivery, &mut Animation, &Address),
+        With<LetterBox>,
+    >,
+    mut letters_query: Query<(Entity, &mut Letters), With<Player>>,
+    mut info_text: ResMut<InfoText>,
+) {
+    for (postman, velocity, mut controller, input, rival) in player_query.iter_mut() {
+        let Some((kind, mut delivery, mut animation, address)) = controller
+            .letter_target
+            .and_then(|target| letter_box_query.get_mut(target).ok())
+        else {
+            continue;
+        };
+
+        if *kind != DeliveryKind::Parcel || *delivery == Delivery::Delivered {
+            continue;
+        }
+
+        if !input.deliver || velocity.length() > PARCEL_MAX_SPEED || controller.damn {
+            controller.parcel_timer.reset();
+            continue;
+        }
+
+        controller.parcel_timer.tick(time.delta());
+        if !rival {
+            info_text.set(format!(
+                "Dropping the parcel... {:.0}%",
+                controller.parcel_timer.fraction() * 100.
+            ));
+        }
+
+        if controller.parcel_timer.finished() {
+            commands.trigger(PlaySfx::Key("launch".into()));
+            animation.play("letter-enter", AnimationRepeat::Count(0));
+            animation.then("close", AnimationRepeat::Loop);
+
+            *delivery = Delivery::Delivered;
+            hand_over(letters_query.iter_mut(), postman, address.0);
+            if let Ok((_, mut letters)) = letters_query.get_mut(postman) {
+                letters.parcels += 1;
+            }
+            controller.parcel_timer.reset();
+            if !rival {
+                info_text.set("Parcel delivered!");
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::fs::read_to_string;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    screen::{playing::CurrentLevel, Screen},
+    ui::prelude::{Containers, RootAnchor},
+    utils::{get_asset_path, path_exist},
+};
+
+use super::{
+    camera::{CameraTarget, MainCamera},
+    circuit::{Circuit, CircuitDirection},
+    input::{read_keyboard, Action, InputSet, PlayerInput},
+    restart::Restart,
+    rng::GameRng,
+    save::{is_level_run, GameSave, RecordFileError},
+    spawn::player::{Player, PlayerMovement},
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(ReplayRecorder, ReplayPlayback)>();
+    app.observe(stop_replay);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        start_playback.run_if(resource_exists::<ReplayPlayback>),
+    );
+    app.add_systems(
+        OnEnter(GameState::End),
+        save_replay.run_if(not(resource_exists::<ReplayPlayback>).and_then(is_level_run)),
+    );
+    app.add_systems(
+        OnEnter(Screen::Title),
+        (|mut commands: Commands| commands.trigger(StopReplay))
+            .run_if(resource_exists::<ReplayPlayback>),
+    );
+    app.add_systems(
+        FixedUpdate,
+        (
+            feed_replay_input.run_if(resource_exists::<ReplayPlayback>),
+            record_input,
+        )
+            .chain()
+            .after(read_keyboard)
+            .in_set(InputSet)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(
+        Update,
+        (
+            viewer_controls,
+            seek_replay,
+            move_free_camera,
+            update_replay_text,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing).and_then(resource_exists::<ReplayPlayback>)),
+    );
+}
+
+const SEEK_SPEED: f32 = 16.;
+const SCRUB_SECONDS: f32 = 5.;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 4.;
+const FREE_CAMERA_SPEED: f32 = 300.;
+
+/// Bike settings used during the run
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone, Copy)]
+pub struct BikeTuning {
+    pub acceleration: f32,
+    pub max_speed: f32,
+    pub friction: f32,
+    pub dash_mul: f32,
+    pub grip: f32,
+    pub drift_grip: f32,
+    pub drift_turn_speed: f32,
+    pub drift_min_speed: f32,
+}
+
+impl BikeTuning {
+    pub fn from_movement(movement: &PlayerMovement) -> Self {
+        Self {
+            acceleration: movement.acceleration,
+            max_speed: movement.max_speed,
+            friction: movement.friction,
+            dash_mul: movement.dash_mul,
+            grip: movement.grip,
+            drift_grip: movement.drift_grip,
+            drift_turn_speed: movement.drift_turn_speed,
+            drift_min_speed: movement.drift_min_speed,
+        }
+    }
+
+    pub fn apply(&self, movement: &mut PlayerMovement) {
+        movement.acceleration = self.acceleration;
+        movement.max_speed = self.max_speed;
+        movement.friction = self.friction;
+        movement.dash_mul = self.dash_mul;
+        movement.grip = self.grip;
+        movement.drift_grip = self.drift_grip;
+        movement.drift_turn_speed = self.drift_turn_speed;
+        movement.drift_min_speed = self.drift_min_speed;
+    }
+}
+
+/// Everything needed to ride a run again, tick by tick
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Replay {
+    pub level: i32,
+    pub level_name: String,
+    pub seed: u64,
+    pub tuning: BikeTuning,
+    pub inputs: Vec<PlayerInput>,
+    #[serde(default)]
+    pub direction: CircuitDirection,
+}
+
+impl Replay {
+    fn path(level_name: &str, direction: CircuitDirection) -> String {
+        get_asset_path(&format!(
+            "data/replays/{}-{}.ron",
+            level_name,
+            direction.name()
+        ))
+    }
+
+    /// Last finished run of a level in a direction
+    pub fn load(level_name: &str, direction: CircuitDirection) -> Option<Self> {
+        let path = Self::path(level_name, direction);
+        if !path_exist(&path) {
+            return None;
+        }
+
+        ron::from_str(&read_to_string(path).ok()?).ok()
+    }
+
+    pub fn save(&self) -> Result<(), RecordFileError> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            super::save::write_ron(&Self::path(&self.level_name, self.direction), self)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inputs of the current run
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct ReplayRecorder {
+    pub tuning: BikeTuning,
+    pub inputs: Vec<PlayerInput>,
+}
+
+/// A replay being watched, it stays between restarts of the level
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub tick: usize,
+    pub paused: bool,
+    pub speed: f32,
+    /// Tick to fast forward to
+    pub seek: Option<usize>,
+    pub free_camera: bool,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            tick: 0,
+            paused: false,
+            speed: 1.,
+            seek: None,
+            free_camera: false,
+        }
+    }
+}
+
+// NOTE - to trigger this event : `commands.trigger(StopReplay);`
+#[derive(Event, Debug)]
+pub struct StopReplay;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+struct ReplayText;
+
+fn start_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    playback.tick = 0;
+    playback.free_camera = false;
+    if playback.seek.is_some() {
+        time.set_relative_speed(SEEK_SPEED);
+    }
+
+    commands
+        .ui_root(RootAnchor::BottomLeft)
+        .insert(StateScoped(Screen::Playing))
+        .with_children(|children| {
+            children.spawn((
+                Name::new("Replay Text"),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        ..default()
+                    },
+                ),
+                ReplayText,
+            ));
+            children.spawn(TextBundle::from_section(
+                "P pause - , . speed - Left Right scrub - C free camera",
+                TextStyle {
+                    font_size: 18.,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn stop_replay(
+    _trigger: Trigger<StopReplay>,
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    time.set_relative_speed(1.);
+    time.unpause();
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+fn record_input(
+    mut recorder: ResMut<ReplayRecorder>,
+    player_query: Query<(&PlayerInput, &PlayerMovement, Ref<Player>)>,
+) {
+    let Ok((input, movement, player)) = player_query.get_single() else {
+        return;
+    };
+    if player.is_added() {
+        recorder.tuning = BikeTuning::from_movement(movement);
+    }
+
+    recorder.inputs.push(*input);
+}
+
+fn feed_replay_input(
+    mut playback: ResMut<ReplayPlayback>,
+    mut player_query: Query<(&mut PlayerInput, &mut PlayerMovement, Ref<Player>)>,
+) {
+    let Ok((mut input, mut movement, player)) = player_query.get_single_mut() else {
+        return;
+    };
+    if player.is_added() {
+        playback.replay.tuning.apply(&mut movement);
+    }
+
+    // nothing is pressed once the replay is over
+    *input = playback
+        .replay
+        .inputs
+        .get(playback.tick)
+        .copied()
+        .unwrap_or_default();
+    playback.tick += 1;
+}
+
+fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    game_rng: Res<GameRng>,
+    game_save: Res<GameSave>,
+    current_level: Res<CurrentLevel>,
+    player_query: Query<&Circuit, With<Player>>,
+) {
+    let Some(level_name) = game_save.level_name(current_level.indice) else {
+        return;
+    };
+    let Ok(circuit) = player_query.get_single() else {
+        return;
+    };
+
+    let replay = Replay {
+        level: current_level.indice,
+        level_name: level_name.into(),
+        seed: game_rng.seed,
+        tuning: recorder.tuning,
+        inputs: recorder.inputs.clone(),
+        direction: circuit.direction,
+    };
+    if let Err(error) = replay.save() {
+        warn!("Could not save the replay: {}", error);
+    }
+}
+
+fn viewer_controls(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    fixed_time: Res<Time<Fixed>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut playback: ResMut<ReplayPlayback>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    if keys.just_pressed(KeyCode::KeyP) {
+        playback.paused = !playback.paused;
+        if playback.paused {
+            time.pause();
+        } else {
+            time.unpause();
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Comma) {
+        playback.speed = (playback.speed / 2.).max(MIN_SPEED);
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        playback.speed = (playback.speed * 2.).min(MAX_SPEED);
+    }
+
+    // scrubbing, going back rides the level again from the start
+    let scrub = (SCRUB_SECONDS / fixed_time.timestep().as_secs_f32()) as usize;
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        playback.seek = Some(playback.tick + scrub);
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        playback.seek = Some(playback.tick.saturating_sub(scrub));
+        commands.trigger(Restart);
+    }
+
+    if keys.just_pressed(KeyCode::KeyC) {
+        playback.free_camera = !playback.free_camera;
+        if let Ok(player) = player_query.get_single() {
+            if playback.free_camera {
+                commands.entity(player).remove::<CameraTarget>();
+            } else {
+                commands.entity(player).insert(CameraTarget);
+            }
+        }
+    }
+}
+
+fn seek_replay(mut time: ResMut<Time<Virtual>>, mut playback: ResMut<ReplayPlayback>) {
+    match playback.seek {
+        Some(target) if playback.tick < target => time.set_relative_speed(SEEK_SPEED),
+        _ => {
+            playback.seek = None;
+            time.set_relative_speed(playback.speed);
+        }
+    }
+}
+
+fn move_free_camera(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    game_save: Res<GameSave>,
+    playback: Res<ReplayPlayback>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !playback.free_camera {
+        return;
+    }
+
+    // moved with the riding keys of the first player
+    let pressed = |action| game_save.bindings.pressed(&keys, 0, action);
+    let mut direction = Vec3::ZERO;
+    if pressed(Action::Accelerate) {
+        direction.y += 1.;
+    }
+    if pressed(Action::Brake) {
+        direction.y -= 1.;
+    }
+    if pressed(Action::SteerLeft) {
+        direction.x -= 1.;
+    }
+    if pressed(Action::SteerRight) {
+        direction.x += 1.;
+    }
+
+    if let Ok(mut transform) = camera_query.get_single_mut() {
+        let movement = transform.rotation * direction.normalize_or_zero();
+        transform.translation += movement * FREE_CAMERA_SPEED * time.delta_seconds();
+    }
+}
+
+fn update_replay_text(
+    fixed_time: Res<Time<Fixed>>,
+    playback: Res<ReplayPlayback>,
+    mut text_query: Query<&mut Text, With<ReplayText>>,
+) {
+    if let Ok(mut text) = text_query.get_single_mut() {
+        let seconds = |tick: usize| tick as f32 * fixed_time.timestep().as_secs_f32();
+        let state = if playback.seek.is_some() {
+            "seeking".to_string()
+        } else if playback.paused {
+            "paused".to_string()
+        } else {
+            format!("x{}", playback.speed)
+        };
+
+        text.sections[0].value = format!(
+            "Replay {} - {:.1}s / {:.1}s",
+            state,
+            seconds(playback.tick),
+            seconds(playback.replay.inputs.len())
+        );
+    }
+}
@@ -2,6 +2,11 @@ use bevy::prelude::*;
 
 use crate::screen::Screen;
 
+use super::{
+    input::{action_pressed, Action},
+    save::GameSave,
+};
+
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<RestartCooldown>();
     app.observe(restart);
@@ -43,8 +48,13 @@ fn tick_cooldown(mut cooldown: ResMut<RestartCooldown>, time: Res<Time>) {
     cooldown.0.tick(time.delta());
 }
 
-fn when_key_pressed(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
-    if keys.pressed(KeyCode::KeyR) {
+fn when_key_pressed(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    game_save: Res<GameSave>,
+) {
+    if action_pressed(&keys, &buttons, &game_save, Action::Restart) {
         commands.trigger(Restart);
     }
 }
@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::screen::{playing::CurrentLevel, Screen};
+
+use super::{
+    circuit::{Circuit, CircuitDuration},
+    input::{read_keyboard, InputSet, PlayerInput},
+    letter::{Delivery, DeliveryKind, Letters},
+    map::road::RoadNetwork,
+    movements::Velocity,
+    rng::GameRng,
+    spawn::player::{Health, Player, PlayerController, PlayerMovement},
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(RivalRace, Rival)>();
+    app.add_systems(OnEnter(Screen::Title), leave_rival_race);
+    app.add_systems(
+        FixedUpdate,
+        drive_rivals
+            .after(read_keyboard)
+            .in_set(InputSet)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(OnEnter(GameState::End), log_rival_runs);
+}
+
+/// How well a rival rides
+#[derive(Reflect, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RivalSkill {
+    Rookie,
+    #[default]
+    Postman,
+    Veteran,
+}
+
+impl RivalSkill {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RivalSkill::Rookie => "Rookie",
+            RivalSkill::Postman => "Postman",
+            RivalSkill::Veteran => "Veteran",
+        }
+    }
+
+    /// The next skill of the settings, none after the best one
+    pub fn next(skill: Option<Self>) -> Option<Self> {
+        match skill {
+            None => Some(RivalSkill::Rookie),
+            Some(RivalSkill::Rookie) => Some(RivalSkill::Postman),
+            Some(RivalSkill::Postman) => Some(RivalSkill::Veteran),
+            Some(RivalSkill::Veteran) => None,
+        }
+    }
+
+    /// Part of the top speed used on straight roads
+    fn speed(&self) -> f32 {
+        match self {
+            RivalSkill::Rookie => 0.7,
+            RivalSkill::Postman => 0.85,
+            RivalSkill::Veteran => 1.,
+        }
+    }
+
+    /// Chance to throw a letter at a house on the way
+    fn throw_chance(&self) -> f64 {
+        match self {
+            RivalSkill::Rookie => 0.6,
+            RivalSkill::Postman => 0.85,
+            RivalSkill::Veteran => 0.98,
+        }
+    }
+
+    /// Swerves per second, most of them end off the road or in an obstacle
+    fn mistake_rate(&self) -> f32 {
+        match self {
+            RivalSkill::Rookie => 1. / 10.,
+            RivalSkill::Postman => 1. / 20.,
+            RivalSkill::Veteran => 1. / 60.,
+        }
+    }
+
+    /// Angle to the road ahead under which the rival keeps straight, in radians
+    fn steering_tolerance(&self) -> f32 {
+        match self {
+            RivalSkill::Rookie => 0.25,
+            RivalSkill::Postman => 0.15,
+            RivalSkill::Veteran => 0.08,
+        }
+    }
+}
+
+/// A computer-controlled postman races the level, removed back on the title screen
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[reflect(Resource)]
+pub struct RivalRace(pub RivalSkill);
+
+/// Drives a postman in place of the keyboard
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct Rival {
+    pub skill: RivalSkill,
+    /// Letter box the rival decided about, and whether it throws at it
+    target: Option<(Entity, bool)>,
+    /// Time left swerving, and the way it swerves
+    swerve: Option<(f32, bool)>,
+}
+
+impl Rival {
+    pub fn new(skill: RivalSkill) -> Self {
+        Self { skill, ..default() }
+    }
+}
+
+/// Slot of the rival, past the local players: no keys and no camera
+pub const RIVAL_SLOT: usize = 2;
+/// The rival starts next to the player, a little further in the start chunk
+pub const RIVAL_START_OFFSET: Vec2 = Vec2::new(32., 0.);
+pub const RIVAL_COLOR: Color = Color::srgb(1., 0.6, 0.6);
+/// Part of the top speed kept in turns
+const TURN_SPEED: f32 = 0.6;
+const SWERVE_DURATION: f32 = 0.6;
+
+fn leave_rival_race(mut commands: Commands) {
+    commands.remove_resource::<RivalRace>();
+}
+
+/// Aim at the middle of the next road chunk, throw at the houses and stop for the parcels
+fn drive_rivals(
+    time: Res<Time>,
+    mut game_rng: ResMut<GameRng>,
+    mut rival_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &PlayerMovement,
+            &PlayerController,
+            &Circuit,
+            &mut PlayerInput,
+            &mut Rival,
+        ),
+        With<Player>,
+    >,
+    letter_box_query: Query<(&Delivery, &DeliveryKind)>,
+) {
+    let delta = time.delta_seconds();
+    let rng = &mut game_rng.rng;
+
+    for (transform, velocity, movement, controller, circuit, mut input, mut rival) in
+        rival_query.iter_mut()
+    {
+        *input = PlayerInput::default();
+
+        // the race starts when the player picks the way
+        if !circuit.direction_chosen || circuit.finished() || controller.damn {
+            continue;
+        }
+        let Some(next) = circuit.cell_at(circuit.progress + 1) else {
+            continue;
+        };
+        let skill = rival.skill;
+
+        // steer toward the road ahead, or the wrong way while swerving
+        let forward = (transform.rotation * Vec3::Y).xy();
+        let to_next = RoadNetwork::center(next) - transform.translation.xy();
+        let angle = forward.angle_between(to_next);
+
+        rival.swerve = match rival.swerve {
+            Some((left, to_left)) if left > delta => Some((left - delta, to_left)),
+            Some(_) => None,
+            None if rng.gen_bool((skill.mistake_rate() * delta).min(1.) as f64) => {
+                Some((SWERVE_DURATION, rng.gen_bool(0.5)))
+            }
+            None => None,
+        };
+        match rival.swerve {
+            Some((_, to_left)) => {
+                input.left = to_left;
+                input.right = !to_left;
+            }
+            None => {
+                input.left = angle > skill.steering_tolerance();
+                input.right = angle < -skill.steering_tolerance();
+            }
+        }
+
+        // slower in turns
+        let mut speed = movement.max_speed * skill.speed();
+        if circuit.in_turn {
+            speed *= TURN_SPEED;
+        }
+        input.forward = velocity.length() < speed;
+
+        // decide once per letter box
+        let Some(target) = controller.letter_target else {
+            rival.target = None;
+            continue;
+        };
+        let Ok((delivery, kind)) = letter_box_query.get(target) else {
+            continue;
+        };
+        if rival.target.map(|(decided, _)| decided) != Some(target) {
+            rival.target = Some((target, rng.gen_bool(skill.throw_chance())));
+        }
+        let delivers = rival.target.is_some_and(|(_, delivers)| delivers);
+
+        if !delivers || *delivery == Delivery::Delivered {
+            continue;
+        }
+        match kind {
+            DeliveryKind::Letter => input.launch = !controller.letter_launched,
+            DeliveryKind::Parcel => {
+                input.forward = false;
+                input.brake = true;
+                input.deliver = true;
+            }
+        }
+    }
+}
+
+/// Runs of the rivals are logged, to playtest a level
+fn log_rival_runs(
+    current_level: Res<CurrentLevel>,
+    rival_query: Query<(&Rival, &Circuit, &CircuitDuration, &Letters, &Health)>,
+) {
+    for (rival, circuit, circuit_duration, letters, health) in rival_query.iter() {
+        info!(
+            "Rival {} on level {}: finished {} in {:.2}s, delivered {}/{}, missed {}, hits {}",
+            rival.skill.name(),
+            current_level.indice,
+            circuit.finished(),
+            circuit_duration.total,
+            letters.delivered(),
+            letters.all,
+            letters.lost,
+            health.hits
+        );
+    }
+}
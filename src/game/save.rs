@@ -1,23 +1,92 @@
 use crate::screen::playing::CurrentLevel;
+use crate::screen::Screen;
 use crate::utils::{get_asset_path, path_exist};
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use serde::{Deserialize, Serialize};
 use std::fs::{read_to_string, File};
 use std::io::Write;
+use thiserror::Error;
 
+use super::circuit::{Circuit, CircuitDirection, CircuitDuration};
+use super::daily::DailyRoute;
+use super::endless::EndlessMode;
+use super::input::KeyBindings;
+use super::lan::LanRace;
+use super::letter::Letters;
 use super::map::ldtk::Project;
+use super::replay::ReplayPlayback;
+use super::rival::{RivalRace, RivalSkill};
+use super::score::ScoreModel;
+use super::spawn::player::{Health, Player};
+use super::versus::VersusMode;
 use super::GameState;
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<GameSave>();
-    app.add_systems(OnEnter(GameState::End), save);
+    app.register_type::<(GameSave, RecordResult)>();
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        count_attempt.run_if(not(resource_exists::<ReplayPlayback>).and_then(is_level_run)),
+    );
+    app.add_systems(OnEnter(GameState::End), save.run_if(is_level_run));
 }
 
+/// The run is one of the levels as designed, the other modes keep their own records
+pub fn is_level_run(
+    endless: Option<Res<EndlessMode>>,
+    daily: Option<Res<DailyRoute>>,
+    versus: Option<Res<VersusMode>>,
+    rival: Option<Res<RivalRace>>,
+    lan: Option<Res<LanRace>>,
+) -> bool {
+    endless.is_none() && daily.is_none() && versus.is_none() && rival.is_none() && lan.is_none()
+}
+
+/// Errors writing a record file, the game goes on without it
+#[derive(Debug, Error)]
+pub enum RecordFileError {
+    #[error("Could not write the file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not serialize RON: {0}")]
+    Ron(#[from] ron::Error),
+}
+
+/// Write a value as RON, creating its directory on the way
+#[cfg(not(target_family = "wasm"))]
+pub fn write_ron(path: &str, value: &impl Serialize) -> Result<(), RecordFileError> {
+    if let Some(directory) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(ron::to_string(value)?.as_bytes())?;
+    Ok(())
+}
+
+/// Number of runs kept in the history of a level
+const HISTORY_LENGTH: usize = 10;
+
 #[derive(Resource, Reflect, Serialize, Deserialize, Default, Debug)]
 #[reflect(Resource)]
 pub struct GameSave {
     pub last_level_passed: i32,
     pub levels: Vec<LevelData>,
+    /// Records by level name
+    #[serde(default)]
+    pub records: HashMap<String, LevelRecord>,
+    /// Don't show the ghost of the best run
+    #[serde(default)]
+    pub hide_ghost: bool,
+    /// Race the levels against a rival of this skill
+    #[serde(default)]
+    pub rival: Option<RivalSkill>,
+    /// Keys of the local players
+    #[serde(default)]
+    pub bindings: KeyBindings,
+    /// Best run of the endless mode
+    #[serde(default)]
+    pub endless_best: Option<EndlessRecord>,
+    /// Records of the daily routes, the most recent day first
+    #[serde(default)]
+    pub daily_records: Vec<DailyRecord>,
 }
 
 impl GameSave {
@@ -43,7 +112,48 @@ impl GameSave {
         game
     }
 
-    pub fn save() {}
+    pub fn save(&self) {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            if let Err(error) = write_ron("assets/data/save.ron", self) {
+                warn!("Could not save the game: {}", error);
+            }
+        }
+    }
+
+    pub fn level_name(&self, level: i32) -> Option<&str> {
+        self.levels
+            .get(level as usize)
+            .map(|level| level.name.as_str())
+    }
+
+    pub fn record(&self, level: i32) -> Option<&LevelRecord> {
+        self.records.get(self.level_name(level)?)
+    }
+
+    fn record_mut(&mut self, level: i32) -> Option<&mut LevelRecord> {
+        let name = self.level_name(level)?.to_string();
+        Some(self.records.entry(name).or_default())
+    }
+
+    pub fn daily_record(&self, day: u64) -> Option<&DailyRecord> {
+        self.daily_records.iter().find(|record| record.day == day)
+    }
+
+    pub fn daily_record_mut(&mut self, day: u64) -> &mut DailyRecord {
+        if self.daily_record(day).is_none() {
+            self.daily_records
+                .insert(0, DailyRecord { day, ..default() });
+            self.daily_records
+                .sort_by_key(|record| std::cmp::Reverse(record.day));
+            self.daily_records.truncate(HISTORY_LENGTH);
+        }
+
+        self.daily_records
+            .iter_mut()
+            .find(|record| record.day == day)
+            .expect("the record of the day was just added")
+    }
 }
 
 #[derive(Resource, Reflect, Serialize, Deserialize, Default, Debug)]
@@ -51,16 +161,297 @@ pub struct LevelData {
     pub name: String,
 }
 
-fn save(mut game_save: ResMut<GameSave>, current_level: Res<CurrentLevel>) {
+/// A finished run of a level
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RunRecord {
+    pub time: f32,
+    #[serde(default)]
+    pub score: i32,
+    pub letters: i32,
+    pub stars: i32,
+    pub direction: CircuitDirection,
+    /// Seconds since the Unix epoch
+    pub date: u64,
+}
+
+impl RunRecord {
+    /// Day of the run as `year-month-day`
+    pub fn date_string(&self) -> String {
+        day_string(local_day(self.date))
+    }
+}
+
+/// Bests of a level ridden in one direction
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct DirectionRecord {
+    /// Fastest finished run
+    pub best: Option<RunRecord>,
+    pub best_letters: i32,
+    pub best_score: i32,
+    pub stars: i32,
+    pub best_lap: Option<f32>,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct LevelRecord {
+    /// Runs started, restarts included
+    pub attempts: u32,
+    #[serde(default)]
+    pub clockwise: DirectionRecord,
+    #[serde(default)]
+    pub anticlockwise: DirectionRecord,
+    /// Last finished runs, the most recent first
+    pub history: Vec<RunRecord>,
+}
+
+impl LevelRecord {
+    /// The two directions are different rides, they keep their own bests
+    pub fn direction(&self, direction: CircuitDirection) -> &DirectionRecord {
+        match direction {
+            CircuitDirection::Clockwise => &self.clockwise,
+            CircuitDirection::AntiClockwise => &self.anticlockwise,
+        }
+    }
+
+    fn direction_mut(&mut self, direction: CircuitDirection) -> &mut DirectionRecord {
+        match direction {
+            CircuitDirection::Clockwise => &mut self.clockwise,
+            CircuitDirection::AntiClockwise => &mut self.anticlockwise,
+        }
+    }
+
+    /// Keep a finished run, returns `true` when it is the fastest one in its direction
+    pub fn add_run(&mut self, run: RunRecord, best_lap: Option<f32>) -> bool {
+        self.history.insert(0, run.clone());
+        self.history.truncate(HISTORY_LENGTH);
+
+        let record = self.direction_mut(run.direction);
+        record.best_letters = record.best_letters.max(run.letters);
+        record.best_score = record.best_score.max(run.score);
+        record.stars = record.stars.max(run.stars);
+        if let Some(lap) = best_lap {
+            record.best_lap = Some(record.best_lap.map_or(lap, |best| best.min(lap)));
+        }
+
+        let new_record = record.best.as_ref().is_none_or(|best| run.time < best.time);
+        if new_record {
+            record.best = Some(run);
+        }
+        new_record
+    }
+}
+
+/// A finished run of the endless mode
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct EndlessRecord {
+    pub score: i32,
+    /// Road chunks ridden
+    pub distance: usize,
+    pub letters: i32,
+    /// Seconds since the Unix epoch
+    pub date: u64,
+}
+
+/// Best run of the daily route of a day
+#[derive(Reflect, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct DailyRecord {
+    /// Days since the Unix epoch
+    pub day: u64,
+    /// Index of the level the route was built on
+    pub level: i32,
+    pub attempts: u32,
+    pub best: Option<RunRecord>,
+}
+
+/// How the last run compares to the records, shown on the end screen
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct RecordResult {
+    pub new_record: bool,
+    pub previous_best: Option<RunRecord>,
+    pub previous_best_lap: Option<f32>,
+}
+
+pub const SECONDS_PER_DAY: u64 = 86400;
+
+/// Days since the Unix epoch as `year-month-day`
+pub fn day_string(days: u64) -> String {
+    // days to civil date, from Howard Hinnant's algorithm
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+/// Days since the Unix epoch of the local date at `seconds`
+pub fn local_day(seconds: u64) -> u64 {
+    (seconds as i64 + local_offset()).div_euclid(SECONDS_PER_DAY as i64) as u64
+}
+
+#[cfg(not(target_family = "wasm"))]
+pub fn now() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Seconds to add to UTC to get the local time
+#[cfg(not(target_family = "wasm"))]
+fn local_offset() -> i64 {
+    chrono::Local::now().offset().local_minus_utc() as i64
+}
+
+#[cfg(target_family = "wasm")]
+pub fn now() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+
+/// Seconds to add to UTC to get the local time, the browser counts minutes the other way
+#[cfg(target_family = "wasm")]
+fn local_offset() -> i64 {
+    -(js_sys::Date::new_0().get_timezone_offset() * 60.) as i64
+}
+
+fn count_attempt(mut game_save: ResMut<GameSave>, current_level: Res<CurrentLevel>) {
+    if let Some(record) = game_save.record_mut(current_level.indice) {
+        record.attempts += 1;
+    }
+}
+
+fn save(
+    mut commands: Commands,
+    mut game_save: ResMut<GameSave>,
+    current_level: Res<CurrentLevel>,
+    score_model: Res<ScoreModel>,
+    player_query: Query<(&Circuit, &CircuitDuration, &Letters, &Health), With<Player>>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
     if game_save.last_level_passed < game_save.levels.len() as i32
         && current_level.indice == game_save.last_level_passed
     {
         game_save.last_level_passed += 1;
     }
-    #[cfg(not(target_family = "wasm"))]
+
+    // watching a replay doesn't make records
+    if let (None, Ok((circuit, circuit_duration, letters, health))) =
+        (playback, player_query.get_single())
     {
-        let mut file = File::create("assets/data/save.ron").unwrap();
-        file.write_all(&ron::to_string(&game_save.as_ref()).unwrap().as_bytes())
-            .unwrap();
+        if let Some(record) = game_save.record_mut(current_level.indice) {
+            let previous_best = record.direction(circuit.direction).best.clone();
+            let previous_best_lap = record.direction(circuit.direction).best_lap;
+            let score = score_model.score(letters, circuit_duration.total, health.hits);
+
+            let new_record = record.add_run(
+                RunRecord {
+                    time: circuit_duration.total,
+                    score: score.points,
+                    letters: letters.delivered(),
+                    stars: score.stars,
+                    direction: circuit.direction,
+                    date: now(),
+                },
+                circuit_duration.best_lap(),
+            );
+
+            commands.insert_resource(RecordResult {
+                new_record,
+                previous_best,
+                previous_best_lap,
+            });
+        }
+    }
+
+    game_save.save();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(time: f32, direction: CircuitDirection) -> RunRecord {
+        RunRecord {
+            time,
+            score: 100,
+            letters: 3,
+            stars: 2,
+            direction,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_add_run_keeps_the_fastest() {
+        let mut record = LevelRecord::default();
+
+        assert!(record.add_run(run(30., CircuitDirection::Clockwise), None));
+        assert!(!record.add_run(run(35., CircuitDirection::Clockwise), None));
+        assert!(record.add_run(run(25., CircuitDirection::Clockwise), None));
+
+        let best = record.direction(CircuitDirection::Clockwise).best.as_ref();
+        assert_eq!(best.map(|best| best.time), Some(25.));
+    }
+
+    #[test]
+    fn test_add_run_per_direction() {
+        let mut record = LevelRecord::default();
+
+        assert!(record.add_run(run(30., CircuitDirection::Clockwise), Some(10.)));
+        // slower, but the first run the other way round
+        assert!(record.add_run(run(40., CircuitDirection::AntiClockwise), Some(12.)));
+
+        let clockwise = record.direction(CircuitDirection::Clockwise);
+        let anticlockwise = record.direction(CircuitDirection::AntiClockwise);
+        assert_eq!(clockwise.best.as_ref().map(|best| best.time), Some(30.));
+        assert_eq!(anticlockwise.best.as_ref().map(|best| best.time), Some(40.));
+        assert_eq!(clockwise.best_lap, Some(10.));
+        assert_eq!(anticlockwise.best_lap, Some(12.));
+    }
+
+    #[test]
+    fn test_add_run_keeps_the_bests_apart() {
+        let mut record = LevelRecord::default();
+
+        record.add_run(run(30., CircuitDirection::Clockwise), Some(10.));
+        let slow = RunRecord {
+            letters: 5,
+            stars: 3,
+            ..run(50., CircuitDirection::Clockwise)
+        };
+        record.add_run(slow, Some(9.));
+
+        let clockwise = record.direction(CircuitDirection::Clockwise);
+        assert_eq!(clockwise.best.as_ref().map(|best| best.time), Some(30.));
+        assert_eq!(clockwise.best_letters, 5);
+        assert_eq!(clockwise.stars, 3);
+        assert_eq!(clockwise.best_lap, Some(9.));
+    }
+
+    #[test]
+    fn test_add_run_history() {
+        let mut record = LevelRecord::default();
+        for i in 0..HISTORY_LENGTH + 2 {
+            record.add_run(run(i as f32, CircuitDirection::Clockwise), None);
+        }
+
+        assert_eq!(record.history.len(), HISTORY_LENGTH);
+        // the last run comes first
+        assert_eq!(record.history[0].time, (HISTORY_LENGTH + 1) as f32);
+    }
+
+    #[test]
+    fn test_day_string() {
+        assert_eq!(day_string(0), "1970-01-01");
+        assert_eq!(day_string(11016), "2000-02-29");
+        assert_eq!(day_string(19723), "2024-01-01");
     }
 }
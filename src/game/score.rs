@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::letter::Letters;
+
+/// Par time used when the level doesn't set one, in seconds
+const DEFAULT_PAR: f32 = 60.;
+
+/// How a run is turned into points and stars, thresholds come from the level
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone)]
+#[reflect(Resource)]
+pub struct ScoreModel {
+    /// Time to beat for the full time bonus, in seconds
+    pub par: f32,
+    /// Points when all letters are posted
+    pub letter_points: i32,
+    /// Bonus when finishing under par, nothing is left at twice the par
+    pub time_points: i32,
+    /// Bonus for each parcel handed over
+    pub parcel_points: i32,
+    /// Bonus for each outgoing mail brought back to the post office
+    #[serde(default)]
+    pub mail_points: i32,
+    /// Points lost for each hit taken
+    pub crash_penalty: i32,
+    /// Points lost for each letter thrown into the wrong letter box
+    #[serde(default)]
+    pub wrong_address_penalty: i32,
+    /// Score needed for one, two and three stars
+    pub star_scores: [i32; 3],
+}
+
+impl Default for ScoreModel {
+    fn default() -> Self {
+        Self {
+            par: DEFAULT_PAR,
+            letter_points: 5000,
+            time_points: 2000,
+            parcel_points: 500,
+            mail_points: 400,
+            crash_penalty: 250,
+            wrong_address_penalty: 300,
+            star_scores: [3000, 5000, 6500],
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    pub points: i32,
+    pub stars: i32,
+}
+
+impl ScoreModel {
+    pub fn score(&self, letters: &Letters, time: f32, hits: i32) -> Score {
+        let posted = letters.delivered();
+        let letter_points = if letters.all > 0 {
+            self.letter_points * posted / letters.all
+        } else {
+            self.letter_points
+        };
+
+        let time_ratio = if self.par > 0. { time / self.par } else { 1. };
+        let time_points = (self.time_points as f32 * (2. - time_ratio).clamp(0., 1.)) as i32;
+
+        let parcel_points = letters.parcels * self.parcel_points;
+        let mail_points = letters.mail_returned * self.mail_points;
+
+        let penalty = hits * self.crash_penalty + letters.wrong * self.wrong_address_penalty;
+        let points = (letter_points + time_points + parcel_points + mail_points - penalty).max(0);
+        let stars = self
+            .star_scores
+            .iter()
+            .filter(|threshold| points >= **threshold)
+            .count() as i32;
+
+        Score { points, stars }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Bag of ten letters with `delivered` of them posted
+    fn letters(delivered: i32) -> Letters {
+        Letters {
+            to_post: 10 - delivered,
+            ..Letters::init(10)
+        }
+    }
+
+    #[test]
+    fn test_three_stars_under_par() {
+        let score = ScoreModel::default().score(&letters(10), 60., 0);
+        assert_eq!(
+            score,
+            Score {
+                points: 7000,
+                stars: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_star_threshold_reached() {
+        // no time bonus left at twice the par
+        let score = ScoreModel::default().score(&letters(10), 120., 0);
+        assert_eq!(
+            score,
+            Score {
+                points: 5000,
+                stars: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_one_star() {
+        let score = ScoreModel::default().score(&letters(5), 60., 0);
+        assert_eq!(
+            score,
+            Score {
+                points: 4500,
+                stars: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_no_star_below_threshold() {
+        let score = ScoreModel::default().score(&letters(5), 60., 7);
+        assert_eq!(
+            score,
+            Score {
+                points: 2750,
+                stars: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_points_never_negative() {
+        let score = ScoreModel::default().score(&letters(0), 120., 50);
+        assert_eq!(
+            score,
+            Score {
+                points: 0,
+                stars: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    game::{
+        collider::Collider,
+        map::{chunk::ChunkConnextion, road::RoadNetwork},
+        movements::Velocity,
+        rng::GameRng,
+        traffic::lane_target,
+    },
+    screen::Screen,
+};
+
+use super::map::ObstacleTag;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Car>();
+    app.observe(spawn_traffic);
+}
+
+// NOTE - to trigger this event : `commands.trigger(SpawnTraffic { .. });`
+#[derive(Event, Debug)]
+pub struct SpawnTraffic {
+    /// Cars per road chunk
+    pub density: f32,
+    /// No car is placed around this position
+    pub start_position: Vec2,
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Car {
+    pub cell: IVec2,
+    pub heading: ChunkConnextion,
+    pub target: Vec2,
+    pub max_speed: f32,
+    pub speed: f32,
+}
+
+const CAR_COLORS: [Color; 4] = [
+    Color::srgb(0.85, 0.2, 0.2),
+    Color::srgb(0.2, 0.45, 0.85),
+    Color::srgb(0.95, 0.8, 0.25),
+    Color::srgb(0.9, 0.9, 0.9),
+];
+
+fn spawn_traffic(
+    trigger: Trigger<SpawnTraffic>,
+    mut commands: Commands,
+    road_network: Res<RoadNetwork>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let rng = &mut game_rng.rng;
+    let event = trigger.event();
+
+    let start_cell = RoadNetwork::cell_at(event.start_position);
+    let mut cells: Vec<IVec2> = road_network
+        .cells()
+        .filter(|cell| (**cell - start_cell).abs().max_element() > 1)
+        .copied()
+        .collect();
+    cells.shuffle(rng);
+
+    let count = (road_network.nodes.len() as f32 * event.density).round() as usize;
+
+    for cell in cells.into_iter().take(count) {
+        let Some(heading) = road_network.connexions(cell).choose(rng).copied() else {
+            continue;
+        };
+
+        let position = lane_target(cell, heading);
+        let color = *CAR_COLORS.choose(rng).unwrap();
+
+        commands
+            .spawn((
+                Name::new("Car"),
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(12., 20.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position.extend(0.08)),
+                    ..default()
+                },
+                Car {
+                    cell,
+                    heading,
+                    target: lane_target(cell + heading.grid_offset(), heading),
+                    max_speed: rng.gen_range(1.2..2.2),
+                    speed: 0.,
+                },
+                Velocity::default(),
+                Collider::new_circle(position, 7.),
+                ObstacleTag,
+                StateScoped(Screen::Playing),
+            ))
+            .with_children(|children| {
+                // windshield
+                children.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::srgba(0.1, 0.15, 0.2, 0.8),
+                        custom_size: Some(Vec2::new(10., 4.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(0., 4., 0.001)),
+                    ..default()
+                });
+            });
+    }
+}
@@ -3,9 +3,11 @@ use std::{default, f32::consts::PI};
 
 use bevy::{
     color::palettes::css::{ORANGE, RED},
+    ecs::system::SystemParam,
     math::VectorSpace,
     prelude::*,
     sprite::Anchor,
+    utils::HashMap,
 };
 use bevy_aseprite_ultra::prelude::{Animation, AsepriteAnimationBundle};
 use rand::Rng;
@@ -13,26 +15,39 @@ use rand::Rng;
 use crate::{
     game::{
         assets::{
-            handles::{AsepriteAssets, HouseAssets, LdtkAssets, TilesetAssets},
+            handles::{AsepriteAssets, FontAssets, HouseAssets, LdtkAssets, TilesetAssets},
             loaders::ldtk::{self, LdtkAsset},
         },
+        circuit::Circuit,
         collider::{Collider, Collision, ExcludeColliderUpdate},
+        daily::DailyRoute,
         house::HouseOrientation,
-        letter::{LetterBox, LetterLaunchZone, Letters},
+        letter::{
+            Address, Delivery, DeliveryKind, HouseWall, LaunchZoneMirror, LetterBox,
+            LetterLaunchZone, Letters,
+        },
+        mail::OutgoingMail,
         map::{
-            builder::MapBuilder,
+            builder::{MapBuilder, Obstacle},
             chunk::{
-                self, ChunkConnextion, ChunkType, RoadChunkType, CHUNK_SIZE, PIXEL_CHUNK_SIZE,
+                self, Chunk, ChunkConnextion, ChunkType, RoadChunkType, CHUNK_SIZE,
+                PIXEL_CHUNK_SIZE,
             },
             ldtk::Project,
+            road::RoadNetwork,
             types::{IntgridType, ObstacleType},
         },
+        obstacle::ObstacleKind,
+        parcel::PARCEL_COLOR,
+        rival::{RivalRace, RIVAL_START_OFFSET},
+        rng::GameRng,
+        versus::{VersusMode, VERSUS_START_OFFSET},
     },
     screen::Screen,
     utils::get_asset_path,
 };
 
-use super::player::SpawnPlayer;
+use super::{car::SpawnTraffic, player::SpawnPlayer};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<(MapTag, ChunkTag, ChunkRoad)>();
@@ -91,14 +106,16 @@ fn spawn_map(
     trigger: Trigger<SpawnMap>,
     mut commands: Commands,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    tilesets: Res<TilesetAssets>,
-    houses: Res<HouseAssets>,
-    aseprites: Res<AsepriteAssets>,
+    assets: ChunkAssets,
 
     ldtk_assets: Res<Assets<LdtkAsset>>,
     ldtks: Res<LdtkAssets>,
+    mut game_rng: ResMut<GameRng>,
+    daily: Option<Res<DailyRoute>>,
+    versus: Option<Res<VersusMode>>,
+    rival: Option<Res<RivalRace>>,
 ) {
-    let mut rng = rand::thread_rng();
+    let rng = &mut game_rng.rng;
 
     // Setup tileset layout
     let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 25, 25, None, None);
@@ -117,13 +134,43 @@ fn spawn_map(
     );
 
     builder.build(&trigger.event().level);
+    if daily.is_some() {
+        builder.shuffle(rng);
+    }
     let map = builder.get_map();
 
-    // Init letters
-    commands.insert_resource(Letters::init(map.count_chunk(ChunkType::House)));
-
-    // Spawn player
-    commands.trigger(SpawnPlayer(map.start_position));
+    // House numbers follow the track, clockwise
+    let mut houses: Vec<(usize, IVec2)> = map
+        .chunks
+        .iter()
+        .filter(|chunk| chunk.chunk_type == ChunkType::House)
+        .map(|chunk| {
+            let cell = RoadNetwork::cell_of(chunk.position);
+            let index = map
+                .track
+                .iter()
+                .position(|road| (*road - cell).abs().element_sum() == 1)
+                .unwrap_or(usize::MAX);
+            (index, cell)
+        })
+        .collect();
+    houses.sort_by_key(|(index, cell)| (*index, cell.y, cell.x));
+    let addresses: HashMap<IVec2, u32> = houses
+        .iter()
+        .enumerate()
+        .map(|(i, (_, cell))| (*cell, i as u32 + 1))
+        .collect();
+    let mut letter_queue = Vec::new();
+    let mut mail = 0;
+
+    commands.insert_resource(map.score_model.clone());
+
+    // Spawn cars on the road network
+    commands.insert_resource(RoadNetwork::from_map(&map));
+    commands.trigger(SpawnTraffic {
+        density: map.traffic_density,
+        start_position: map.start_position,
+    });
 
     let map_entity = commands
         .spawn((
@@ -136,379 +183,563 @@ fn spawn_map(
 
     // Spawn chunks
     let mut chunks = Vec::new();
-    for (i, chunk) in map.chunks.iter().enumerate() {
+    for chunk in map.chunks.iter() {
         if chunk.is_empty() {
             continue;
         }
 
-        // calc rotation and translation of chunk
-        let mut angle = 0.;
-        let mut translation = chunk.position;
+        let address = addresses
+            .get(&RoadNetwork::cell_of(chunk.position))
+            .copied()
+            .unwrap_or_default();
+        let spawned = spawn_chunk(
+            &mut commands,
+            &assets,
+            &texture_atlas_layout,
+            chunk,
+            HouseSetup {
+                address,
+                parcel_ratio: map.parcel_ratio,
+                mail_ratio: map.mail_ratio,
+            },
+            rng,
+        );
 
-        if chunk.flip_x && chunk.flip_y {
-            angle = std::f32::consts::PI;
-            translation.x += PIXEL_CHUNK_SIZE - 16.;
-            translation.y -= PIXEL_CHUNK_SIZE - 16.;
-        } else {
-            if chunk.flip_x {
-                angle = -std::f32::consts::PI / 2.;
-                translation.x += PIXEL_CHUNK_SIZE - 16.;
-            }
-            if chunk.flip_y {
-                angle = std::f32::consts::PI / 2.;
-                translation.y -= PIXEL_CHUNK_SIZE - 16.;
-            }
+        if spawned.delivery == Some(DeliveryKind::Letter) {
+            letter_queue.push(address);
         }
+        if spawned.mail {
+            mail += 1;
+        }
+        chunks.push(spawned.entity);
+    }
 
-        let rotation = Quat::from_axis_angle(Vec3::Z, angle);
+    // Spawn player, with its track progress and letters
+    letter_queue.sort();
+    let circuit = Circuit::new(map.track.clone(), map.checkpoints.clone(), map.laps);
+    let letters = Letters::init(map.count_chunk(ChunkType::House))
+        .with_queue(letter_queue)
+        .with_mail(mail);
+
+    if versus.is_some() {
+        commands.trigger(
+            SpawnPlayer::new(
+                map.start_position + VERSUS_START_OFFSET,
+                circuit.clone(),
+                letters.clone(),
+            )
+            .with_slot(1),
+        );
+    }
+    if let Some(race) = rival {
+        commands.trigger(
+            SpawnPlayer::new(
+                map.start_position + RIVAL_START_OFFSET,
+                circuit.clone(),
+                letters.clone(),
+            )
+            .with_rival(race.0),
+        );
+    }
+    commands.trigger(SpawnPlayer::new(map.start_position, circuit, letters));
 
-        let chunk_entity = commands
-            .spawn((
-                SpatialBundle {
-                    transform: Transform::from_translation(translation.extend(-0.02))
-                        .with_rotation(rotation),
-                    ..default()
-                },
-                ChunkConnextions(chunk.connextions.clone()),
-                ChunkTag,
-            ))
-            .with_children(|children| {
-                // Tiles spawn
-                for y in 0..CHUNK_SIZE {
-                    for x in 0..CHUNK_SIZE {
-                        let intgrid = chunk.intgrid_at(x, y).unwrap();
-                        let tile = chunk.tile_at(x, y).unwrap();
-
-                        let mut a = children.spawn((
-                            Name::new("Tile"),
-                            SpriteBundle {
-                                sprite: Sprite {
-                                    flip_x: tile.flip_x,
-                                    flip_y: tile.flip_y,
-                                    ..default()
-                                },
-                                texture: tilesets.get("tiles").clone(),
-                                transform: Transform::from_translation(Vec3::new(
-                                    x as f32 * 16.,
-                                    y as f32 * -16.,
-                                    0.,
-                                )),
-                                ..default()
-                            },
-                            TextureAtlas {
-                                layout: texture_atlas_layout.clone(),
-                                index: tile.value as usize,
-                            },
-                        ));
+    // Spawn chunks
+    for chunk in map.decor_chunks.iter() {
+        if chunk.is_empty() {
+            continue;
+        }
 
-                        if *intgrid == IntgridType::Empty
-                            && matches!(chunk.chunk_type, ChunkType::Road(_))
-                        {
-                            a.insert((NotRoadTile, Collider::rect(8., 8.)));
-                        }
-                    }
-                }
-            })
-            .id();
+        chunks.push(spawn_decor_chunk(&mut commands, &assets, chunk, rng));
+    }
 
-        if chunk.is_end {
-            commands.entity(chunk_entity).insert(EndChunk);
+    // Spawn obstacles
+    commands.entity(map_entity).with_children(|children| {
+        for obstacle in map.obstacles.iter() {
+            spawn_obstacle(children, &assets, obstacle, rng);
         }
+    });
 
-        match &chunk.chunk_type {
-            ChunkType::Road(road_type) => {
-                // get chunk orientation
-                let orientation = match road_type {
-                    RoadChunkType::Horizontal => ChunkRoad::Horizontal,
-                    RoadChunkType::HorizontalJUp => ChunkRoad::Horizontal,
-                    RoadChunkType::HorizontalJDown => ChunkRoad::Horizontal,
-                    RoadChunkType::Vertical => ChunkRoad::Vertical,
-                    RoadChunkType::VerticalJLeft => ChunkRoad::Vertical,
-                    RoadChunkType::VerticalJRight => ChunkRoad::Vertical,
-                    RoadChunkType::Turn => ChunkRoad::Turn,
-                };
-
-                commands.entity(chunk_entity).insert((
-                    orientation.clone(),
-                    Collider::new_rect_corners(
-                        chunk.position + Vec2::new(-8., 8.),
-                        chunk.position
-                            + Vec2::new(PIXEL_CHUNK_SIZE, -PIXEL_CHUNK_SIZE)
-                            + Vec2::new(-8., 8.),
-                    ),
-                ));
-            }
-            ChunkType::PostOffice => {
-                commands.entity(chunk_entity).insert((
-                    PostOffice,
-                    Collider::new_rect_corners(
-                        chunk.position + Vec2::new(-8., 8.),
-                        chunk.position
-                            + Vec2::new(PIXEL_CHUNK_SIZE, -PIXEL_CHUNK_SIZE)
-                            + Vec2::new(-8., 8.),
-                    ),
-                ));
-
-                // Spawn a lot of letters
-                commands
-                    .spawn((
-                        Name::new("Post Office Letters"),
-                        SpatialBundle {
-                            transform: Transform::from_translation(chunk.position.extend(0.02)),
-                            ..default()
-                        },
-                        StateScoped(Screen::Playing),
-                    ))
-                    .with_children(|children| {
-                        let mut rng = rand::thread_rng();
-                        for _ in 0..100 {
-                            let translation = Vec2::new(
-                                rng.gen_range(0..PIXEL_CHUNK_SIZE as u32) as f32,
-                                -(rng.gen_range(0..PIXEL_CHUNK_SIZE as u32) as f32) + 16.,
-                            );
-                            let angle = rng.gen_range(1..6) as f32 - 0.25;
-                            let scale = Vec2::splat(rng.gen_range(6..10) as f32 / 10.);
-
-                            let tag = if rng.gen_ratio(2, 10) {
-                                "craft"
-                            } else {
-                                "small"
-                            };
-
-                            children.spawn(
-                                (AsepriteAnimationBundle {
-                                    aseprite: aseprites.get("letter"),
-                                    animation: Animation::default().with_tag(tag),
-                                    transform: Transform::from_translation(translation.extend(0.))
-                                        .with_rotation(Quat::from_axis_angle(Vec3::Z, angle))
-                                        .with_scale(scale.extend(0.)),
-                                    ..default()
-                                }),
-                            );
-                        }
-                    });
-            }
-            ChunkType::House => {
-                // House spawn
-                let house = &chunk.house.clone().unwrap();
-                let number = rng.gen_range(1..=6);
-                let house_postition = house.position * Vec2::new(1., -1.);
-                let house_entity = commands
-                    .spawn((
-                        Name::new("House"),
+    commands.entity(map_entity).push_children(chunks.as_slice());
+}
+
+/// Assets used to spawn the chunks of a map
+#[derive(SystemParam)]
+pub struct ChunkAssets<'w> {
+    tilesets: Res<'w, TilesetAssets>,
+    houses: Res<'w, HouseAssets>,
+    aseprites: Res<'w, AsepriteAssets>,
+    fonts: Res<'w, FontAssets>,
+}
+
+/// What the house of a chunk gets
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HouseSetup {
+    pub address: u32,
+    /// Chance of the house waiting for a parcel
+    pub parcel_ratio: f32,
+    /// Chance of the letter box holding outgoing mail
+    pub mail_ratio: f32,
+}
+
+#[derive(Debug)]
+pub struct SpawnedChunk {
+    pub entity: Entity,
+    /// What the house of the chunk expects
+    pub delivery: Option<DeliveryKind>,
+    /// The letter box holds outgoing mail
+    pub mail: bool,
+}
+
+/// Spawn the tiles of a chunk, with the house, letter box and launch zone of a house chunk
+pub fn spawn_chunk(
+    commands: &mut Commands,
+    assets: &ChunkAssets,
+    layout: &Handle<TextureAtlasLayout>,
+    chunk: &Chunk,
+    setup: HouseSetup,
+    rng: &mut impl Rng,
+) -> SpawnedChunk {
+    let mut delivery = None;
+    let mut mail = false;
+
+    // calc rotation and translation of chunk
+    let mut angle = 0.;
+    let mut translation = chunk.position;
+
+    if chunk.flip_x && chunk.flip_y {
+        angle = std::f32::consts::PI;
+        translation.x += PIXEL_CHUNK_SIZE - 16.;
+        translation.y -= PIXEL_CHUNK_SIZE - 16.;
+    } else {
+        if chunk.flip_x {
+            angle = -std::f32::consts::PI / 2.;
+            translation.x += PIXEL_CHUNK_SIZE - 16.;
+        }
+        if chunk.flip_y {
+            angle = std::f32::consts::PI / 2.;
+            translation.y -= PIXEL_CHUNK_SIZE - 16.;
+        }
+    }
+
+    let rotation = Quat::from_axis_angle(Vec3::Z, angle);
+
+    let chunk_entity = commands
+        .spawn((
+            SpatialBundle {
+                transform: Transform::from_translation(translation.extend(-0.02))
+                    .with_rotation(rotation),
+                ..default()
+            },
+            ChunkConnextions(chunk.connextions.clone()),
+            ChunkTag,
+        ))
+        .with_children(|children| {
+            // Tiles spawn
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let intgrid = chunk.intgrid_at(x, y).unwrap();
+                    let tile = chunk.tile_at(x, y).unwrap();
+
+                    let mut a = children.spawn((
+                        Name::new("Tile"),
                         SpriteBundle {
                             sprite: Sprite {
-                                anchor: Anchor::BottomRight,
+                                flip_x: tile.flip_x,
+                                flip_y: tile.flip_y,
                                 ..default()
                             },
-                            texture: houses.get(number.to_string().as_str()),
-                            transform: Transform::from_translation(house_postition.extend(0.001)),
+                            texture: assets.tilesets.get("tiles").clone(),
+                            transform: Transform::from_translation(Vec3::new(
+                                x as f32 * 16.,
+                                y as f32 * -16.,
+                                0.,
+                            )),
                             ..default()
                         },
-                    ))
-                    .id();
-
-                // Spawn letter box
-                let letter_box_position = house_postition + Vec2::new(16. * 2., 16.);
-                let letter_box_entity = commands
-                    .spawn((
-                        Name::new("Letter Box"),
-                        AsepriteAnimationBundle {
-                            aseprite: aseprites.get("letter-box"),
-                            animation: Animation::default().with_tag("close"),
-                            transform: Transform::from_translation(letter_box_position.extend(0.)),
-                            ..default()
+                        TextureAtlas {
+                            layout: layout.clone(),
+                            index: tile.value as usize,
                         },
-                        Collider::rect(8., 8.),
-                        LetterBox,
-                    ))
-                    .id();
+                    ));
 
-                commands
-                    .entity(chunk_entity)
-                    .push_children(&[house_entity, letter_box_entity]);
-
-                // Spawn letter launch zone
-                let collider_for_letter_boooox;
-                let collider_size = Vec2::new(
-                    PIXEL_CHUNK_SIZE / 2.,
-                    PIXEL_CHUNK_SIZE / 2. + PIXEL_CHUNK_SIZE / 4.,
-                );
-                let angle_mul = if chunk.has_connexion(ChunkConnextion::Right) {
-                    collider_for_letter_boooox = commands
-                        .spawn((Collider::new_rect(
-                            chunk.position + (Vec2::X * (PIXEL_CHUNK_SIZE + PIXEL_CHUNK_SIZE / 2.)),
-                            collider_size,
-                        ),))
-                        .id();
-                    0
-                } else if chunk.has_connexion(ChunkConnextion::Bottom) {
-                    collider_for_letter_boooox = commands
-                        .spawn((Collider::new_rect(
-                            chunk.position - (Vec2::Y * (PIXEL_CHUNK_SIZE + PIXEL_CHUNK_SIZE / 2.)),
-                            Vec2::new(collider_size.y, collider_size.x),
-                        ),))
-                        .id();
-                    1
-                } else if chunk.has_connexion(ChunkConnextion::Left) {
-                    collider_for_letter_boooox = commands
-                        .spawn((Collider::new_rect(
-                            chunk.position - Vec2::X * PIXEL_CHUNK_SIZE / 2.,
-                            collider_size,
-                        ),))
-                        .id();
-                    2
-                } else if chunk.has_connexion(ChunkConnextion::Top) {
-                    collider_for_letter_boooox = commands
-                        .spawn((Collider::new_rect(
-                            chunk.position + Vec2::Y * PIXEL_CHUNK_SIZE / 2.,
-                            Vec2::new(collider_size.y, collider_size.x),
-                        ),))
-                        .id();
-                    3
-                } else {
-                    collider_for_letter_boooox = commands
-                        .spawn((Collider::new_rect(Vec2::ZERO, collider_size),))
-                        .id();
-                    0
-                };
-                commands.entity(collider_for_letter_boooox).insert((
-                    Transform::default(),
-                    ExcludeColliderUpdate,
-                    LetterLaunchZone(letter_box_entity),
-                ));
-                commands
-                    .entity(chunk_entity)
-                    .insert(HouseOrientation { angle_mul })
-                    .push_children(&[collider_for_letter_boooox]);
+                    if *intgrid == IntgridType::Empty
+                        && matches!(chunk.chunk_type, ChunkType::Road(_))
+                    {
+                        a.insert((NotRoadTile, Collider::rect(8., 8.)));
+                    }
+                }
             }
-            ChunkType::Decor(_) => {}
-            _ => {}
-        }
-
-        commands
-            .entity(chunk_entity)
-            .insert(Name::new(format!("Chunk {:?}", chunk.chunk_type)));
+        })
+        .id();
 
-        chunks.push(chunk_entity);
+    if chunk.is_end {
+        commands.entity(chunk_entity).insert(EndChunk);
     }
 
-    // Spawn chunks
-    for (_, chunk) in map.decor_chunks.iter().enumerate() {
-        if chunk.is_empty() {
-            continue;
-        }
+    match &chunk.chunk_type {
+        ChunkType::Road(road_type) => {
+            // get chunk orientation
+            let orientation = match road_type {
+                RoadChunkType::Horizontal => ChunkRoad::Horizontal,
+                RoadChunkType::HorizontalJUp => ChunkRoad::Horizontal,
+                RoadChunkType::HorizontalJDown => ChunkRoad::Horizontal,
+                RoadChunkType::Vertical => ChunkRoad::Vertical,
+                RoadChunkType::VerticalJLeft => ChunkRoad::Vertical,
+                RoadChunkType::VerticalJRight => ChunkRoad::Vertical,
+                RoadChunkType::Turn => ChunkRoad::Turn,
+            };
 
-        let chunk_entity = commands
-            .spawn((
-                Name::new("Decor"),
-                SpatialBundle {
-                    transform: Transform::from_translation(chunk.position.extend(-0.02)),
-                    ..default()
-                },
-                ChunkConnextions(chunk.connextions.clone()),
-                ChunkTag,
-            ))
-            .id();
+            commands.entity(chunk_entity).insert((
+                orientation.clone(),
+                Collider::new_rect_corners(
+                    chunk.position + Vec2::new(-8., 8.),
+                    chunk.position
+                        + Vec2::new(PIXEL_CHUNK_SIZE, -PIXEL_CHUNK_SIZE)
+                        + Vec2::new(-8., 8.),
+                ),
+            ));
+        }
+        ChunkType::PostOffice => {
+            commands.entity(chunk_entity).insert((
+                PostOffice,
+                Collider::new_rect_corners(
+                    chunk.position + Vec2::new(-8., 8.),
+                    chunk.position
+                        + Vec2::new(PIXEL_CHUNK_SIZE, -PIXEL_CHUNK_SIZE)
+                        + Vec2::new(-8., 8.),
+                ),
+            ));
 
-        // Spawn trees
-        commands.entity(chunk_entity).with_children(|children| {
-            children
+            // Spawn a lot of letters
+            commands
                 .spawn((
+                    Name::new("Post Office Letters"),
                     SpatialBundle {
-                        transform: Transform::from_translation(Vec3::new(
-                            PIXEL_CHUNK_SIZE / 2.,
-                            -PIXEL_CHUNK_SIZE / 2.,
-                            0.,
-                        )),
+                        transform: Transform::from_translation(chunk.position.extend(0.02)),
                         ..default()
                     },
-                    FollowPlayerRotation,
+                    StateScoped(Screen::Playing),
                 ))
                 .with_children(|children| {
-                    for tree in chunk.trees.iter() {
-                        children.spawn((
-                            Name::new("Tree"),
-                            AsepriteAnimationBundle {
-                                aseprite: aseprites.get("trees"),
-                                animation: Animation::default()
-                                    .with_tag(rng.gen_range(1..=4).to_string().as_str()),
-                                transform: Transform::from_translation(
-                                    (tree.0
-                                        + Vec2::new(-PIXEL_CHUNK_SIZE / 2., PIXEL_CHUNK_SIZE / 2.))
-                                    .extend(0.),
-                                ),
+                    for _ in 0..100 {
+                        let translation = Vec2::new(
+                            rng.gen_range(0..PIXEL_CHUNK_SIZE as u32) as f32,
+                            -(rng.gen_range(0..PIXEL_CHUNK_SIZE as u32) as f32) + 16.,
+                        );
+                        let angle = rng.gen_range(1..6) as f32 - 0.25;
+                        let scale = Vec2::splat(rng.gen_range(6..10) as f32 / 10.);
+
+                        let tag = if rng.gen_ratio(2, 10) {
+                            "craft"
+                        } else {
+                            "small"
+                        };
+
+                        children.spawn(
+                            (AsepriteAnimationBundle {
+                                aseprite: assets.aseprites.get("letter"),
+                                animation: Animation::default().with_tag(tag),
+                                transform: Transform::from_translation(translation.extend(0.))
+                                    .with_rotation(Quat::from_axis_angle(Vec3::Z, angle))
+                                    .with_scale(scale.extend(0.)),
                                 ..default()
-                            },
-                        ));
+                            }),
+                        );
                     }
                 });
-        });
-
-        chunks.push(chunk_entity);
-    }
-
-    println!("{}, {}", map.chunks.len(), map.decor_chunks.len());
-
-    // Spawn obstacles
-    commands.entity(map_entity).with_children(|children| {
-        for obstacle in map.obstacles.iter() {
-            let aseprite = match &obstacle.obstacle_type {
-                ObstacleType::RoadWork => "road-work",
-                ObstacleType::WatterPuddle => "water-puddle",
-                _ => "",
-            };
-
-            let is_horizontal = obstacle.chunk.has_connexion(ChunkConnextion::Left)
-                || obstacle.chunk.has_connexion(ChunkConnextion::Right);
+        }
+        ChunkType::House => {
+            // House spawn
+            let house = &chunk.house.clone().unwrap();
+            let number = rng.gen_range(1..=6);
+            let house_postition = house.position * Vec2::new(1., -1.);
+            let house_entity = commands
+                .spawn((
+                    Name::new("House"),
+                    SpriteBundle {
+                        sprite: Sprite {
+                            anchor: Anchor::BottomRight,
+                            ..default()
+                        },
+                        texture: assets.houses.get(number.to_string().as_str()),
+                        transform: Transform::from_translation(house_postition.extend(0.001)),
+                        ..default()
+                    },
+                ))
+                .with_children(|children| {
+                    // the building, the sprite is anchored at its bottom right corner
+                    children.spawn((
+                        Name::new("House Wall"),
+                        SpatialBundle::from_transform(Transform::from_xyz(-64., 80., 0.)),
+                        Collider::new_rect_half_size(Vec2::new(40., 36.)),
+                        HouseWall,
+                    ));
+                })
+                .id();
+
+            // Spawn letter box
+            let address = setup.address;
+            let letter_box_position = house_postition + Vec2::new(16. * 2., 16.);
+            let letter_box_entity = commands
+                .spawn((
+                    Name::new("Letter Box"),
+                    AsepriteAnimationBundle {
+                        aseprite: assets.aseprites.get("letter-box"),
+                        animation: Animation::default().with_tag("close"),
+                        transform: Transform::from_translation(letter_box_position.extend(0.)),
+                        ..default()
+                    },
+                    Collider::rect(8., 8.),
+                    LetterBox,
+                    Address(address),
+                    Delivery::default(),
+                ))
+                .with_children(|children| {
+                    children.spawn((
+                        Name::new("House Number"),
+                        Text2dBundle {
+                            text: Text::from_section(
+                                address.to_string(),
+                                TextStyle {
+                                    font_size: 12.,
+                                    color: Color::WHITE,
+                                    font: assets.fonts.get("gamer"),
+                                },
+                            ),
+                            transform: Transform::from_xyz(0., -14., 0.1)
+                                .with_scale(Vec3::splat(0.5)),
+                            ..default()
+                        },
+                    ));
+                })
+                .id();
 
-            let place_rand = if rng.gen_ratio(1, 2) {
-                16. + 8.
+            if rng.gen_bool(setup.parcel_ratio.clamp(0., 1.) as f64) {
+                delivery = Some(DeliveryKind::Parcel);
+                commands
+                    .entity(letter_box_entity)
+                    .insert(DeliveryKind::Parcel)
+                    .with_children(|children| {
+                        // show the house is waiting for a parcel
+                        children.spawn((
+                            Name::new("Parcel Sign"),
+                            SpriteBundle {
+                                sprite: Sprite {
+                                    color: PARCEL_COLOR,
+                                    custom_size: Some(Vec2::new(8., 6.)),
+                                    ..default()
+                                },
+                                transform: Transform::from_xyz(0., 20., 0.1),
+                                ..default()
+                            },
+                        ));
+                    });
             } else {
-                -1. * (16. + 8.)
-            };
+                commands
+                    .entity(letter_box_entity)
+                    .insert(DeliveryKind::Letter);
+                delivery = Some(DeliveryKind::Letter);
+            }
+
+            if rng.gen_bool(setup.mail_ratio.clamp(0., 1.) as f64) {
+                mail = true;
+                commands.entity(letter_box_entity).insert(OutgoingMail);
+            }
 
-            let place = if is_horizontal {
-                Vec2::Y * place_rand
+            commands
+                .entity(chunk_entity)
+                .push_children(&[house_entity, letter_box_entity]);
+
+            // Spawn letter launch zone
+            let collider_for_letter_boooox;
+            let collider_size = Vec2::new(
+                PIXEL_CHUNK_SIZE / 2.,
+                PIXEL_CHUNK_SIZE / 2. + PIXEL_CHUNK_SIZE / 4.,
+            );
+            let angle_mul = if chunk.has_connexion(ChunkConnextion::Right) {
+                collider_for_letter_boooox = commands
+                    .spawn((Collider::new_rect(
+                        chunk.position + (Vec2::X * (PIXEL_CHUNK_SIZE + PIXEL_CHUNK_SIZE / 2.)),
+                        collider_size,
+                    ),))
+                    .id();
+                0
+            } else if chunk.has_connexion(ChunkConnextion::Bottom) {
+                collider_for_letter_boooox = commands
+                    .spawn((Collider::new_rect(
+                        chunk.position - (Vec2::Y * (PIXEL_CHUNK_SIZE + PIXEL_CHUNK_SIZE / 2.)),
+                        Vec2::new(collider_size.y, collider_size.x),
+                    ),))
+                    .id();
+                1
+            } else if chunk.has_connexion(ChunkConnextion::Left) {
+                collider_for_letter_boooox = commands
+                    .spawn((Collider::new_rect(
+                        chunk.position - Vec2::X * PIXEL_CHUNK_SIZE / 2.,
+                        collider_size,
+                    ),))
+                    .id();
+                2
+            } else if chunk.has_connexion(ChunkConnextion::Top) {
+                collider_for_letter_boooox = commands
+                    .spawn((Collider::new_rect(
+                        chunk.position + Vec2::Y * PIXEL_CHUNK_SIZE / 2.,
+                        Vec2::new(collider_size.y, collider_size.x),
+                    ),))
+                    .id();
+                3
             } else {
-                Vec2::X * place_rand
+                collider_for_letter_boooox = commands
+                    .spawn((Collider::new_rect(Vec2::ZERO, collider_size),))
+                    .id();
+                0
             };
+            commands.entity(collider_for_letter_boooox).insert((
+                Transform::default(),
+                ExcludeColliderUpdate,
+                LetterLaunchZone(letter_box_entity),
+                LaunchZoneMirror {
+                    pivot: chunk.position
+                        + Vec2::new(PIXEL_CHUNK_SIZE / 2. - 8., -PIXEL_CHUNK_SIZE / 2. + 8.),
+                    axis: if angle_mul % 2 == 0 { Vec2::Y } else { Vec2::X },
+                },
+            ));
+            commands
+                .entity(chunk_entity)
+                .insert(HouseOrientation { angle_mul })
+                .push_children(&[collider_for_letter_boooox]);
+        }
+        ChunkType::Decor(_) => {}
+        _ => {}
+    }
 
-            let angle = if is_horizontal { PI / 2. } else { 0. };
+    commands
+        .entity(chunk_entity)
+        .insert(Name::new(format!("Chunk {:?}", chunk.chunk_type)));
 
-            let tag = match &obstacle.obstacle_type {
-                ObstacleType::RoadWork => None,
-                ObstacleType::WatterPuddle => Some(rng.gen_range(1..=2).to_string()),
-                _ => None,
-            };
+    SpawnedChunk {
+        entity: chunk_entity,
+        delivery,
+        mail,
+    }
+}
 
-            let collider = match &obstacle.obstacle_type {
-                ObstacleType::RoadWork => {
-                    Collider::new_rect(obstacle.chunk_center + place, Vec2::new(10., 15.))
-                }
-                ObstacleType::WatterPuddle => {
-                    Collider::new_circle(obstacle.chunk_center + place, 10.)
-                }
-                _ => Collider::new_rect(obstacle.chunk_center + place, Vec2::splat(10.)),
-            };
+/// Spawn a decor chunk and its trees
+pub fn spawn_decor_chunk(
+    commands: &mut Commands,
+    assets: &ChunkAssets,
+    chunk: &Chunk,
+    rng: &mut impl Rng,
+) -> Entity {
+    let chunk_entity = commands
+        .spawn((
+            Name::new("Decor"),
+            SpatialBundle {
+                transform: Transform::from_translation(chunk.position.extend(-0.02)),
+                ..default()
+            },
+            ChunkConnextions(chunk.connextions.clone()),
+            ChunkTag,
+        ))
+        .id();
 
-            children.spawn((
-                Name::new(format!("{:?} Obstacle", obstacle.obstacle_type)),
-                AsepriteAnimationBundle {
-                    aseprite: aseprites.get(aseprite),
-                    animation: Animation { tag, ..default() },
-                    transform: Transform::from_translation(
-                        (obstacle.chunk_center + place).extend(0.),
-                    )
-                    .with_rotation(Quat::from_axis_angle(Vec3::Z, angle)),
+    // Spawn trees
+    commands.entity(chunk_entity).with_children(|children| {
+        children
+            .spawn((
+                SpatialBundle {
+                    transform: Transform::from_translation(Vec3::new(
+                        PIXEL_CHUNK_SIZE / 2.,
+                        -PIXEL_CHUNK_SIZE / 2.,
+                        0.,
+                    )),
                     ..default()
                 },
-                ObstacleTag,
-                collider,
-                // Collider::new_rect(obstacle.chunk_center + place, Vec2::splat(10.)),
-            ));
-        }
+                FollowPlayerRotation,
+            ))
+            .with_children(|children| {
+                for tree in chunk.trees.iter() {
+                    children.spawn((
+                        Name::new("Tree"),
+                        AsepriteAnimationBundle {
+                            aseprite: assets.aseprites.get("trees"),
+                            animation: Animation::default()
+                                .with_tag(rng.gen_range(1..=4).to_string().as_str()),
+                            transform: Transform::from_translation(
+                                (tree.0 + Vec2::new(-PIXEL_CHUNK_SIZE / 2., PIXEL_CHUNK_SIZE / 2.))
+                                    .extend(0.),
+                            ),
+                            ..default()
+                        },
+                    ));
+                }
+            });
     });
 
-    commands.entity(map_entity).push_children(chunks.as_slice());
+    chunk_entity
+}
+
+pub fn spawn_obstacle(
+    children: &mut ChildBuilder,
+    assets: &ChunkAssets,
+    obstacle: &Obstacle,
+    rng: &mut impl Rng,
+) {
+    let aseprite = match &obstacle.obstacle_type {
+        ObstacleType::RoadWork => "road-work",
+        ObstacleType::WatterPuddle => "water-puddle",
+        ObstacleType::ManholeCover => "manhole-cover",
+        ObstacleType::WorkCone => "work-cone",
+        ObstacleType::Empty => return,
+    };
+
+    let is_horizontal = obstacle.chunk.has_connexion(ChunkConnextion::Left)
+        || obstacle.chunk.has_connexion(ChunkConnextion::Right);
+
+    let place_rand = if rng.gen_ratio(1, 2) {
+        16. + 8.
+    } else {
+        -1. * (16. + 8.)
+    };
+
+    let place = if is_horizontal {
+        Vec2::Y * place_rand
+    } else {
+        Vec2::X * place_rand
+    };
+
+    let angle = if is_horizontal { PI / 2. } else { 0. };
+
+    let tag = match &obstacle.obstacle_type {
+        ObstacleType::RoadWork => None,
+        ObstacleType::WatterPuddle => Some(rng.gen_range(1..=2).to_string()),
+        _ => None,
+    };
+
+    let collider = match &obstacle.obstacle_type {
+        ObstacleType::RoadWork => {
+            Collider::new_rect(obstacle.chunk_center + place, Vec2::new(10., 15.))
+        }
+        ObstacleType::WatterPuddle => Collider::new_circle(obstacle.chunk_center + place, 10.),
+        ObstacleType::ManholeCover => Collider::new_circle(obstacle.chunk_center + place, 7.),
+        ObstacleType::WorkCone => {
+            Collider::new_rect(obstacle.chunk_center + place, Vec2::splat(4.))
+        }
+        _ => Collider::new_rect(obstacle.chunk_center + place, Vec2::splat(10.)),
+    };
+
+    children.spawn((
+        Name::new(format!("{:?} Obstacle", obstacle.obstacle_type)),
+        AsepriteAnimationBundle {
+            aseprite: assets.aseprites.get(aseprite),
+            animation: Animation { tag, ..default() },
+            transform: Transform::from_translation((obstacle.chunk_center + place).extend(0.))
+                .with_rotation(Quat::from_axis_angle(Vec3::Z, angle)),
+            ..default()
+        },
+        ObstacleTag,
+        ObstacleKind(obstacle.obstacle_type),
+        collider,
+        // Collider::new_rect(obstacle.chunk_center + place, Vec2::splat(10.)),
+    ));
 }
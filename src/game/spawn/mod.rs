@@ -1,9 +1,10 @@
 use bevy::prelude::*;
 
+pub mod car;
 pub mod level;
 pub mod map;
 pub mod player;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((level::plugin, player::plugin, map::plugin));
+    app.add_plugins((level::plugin, player::plugin, map::plugin, car::plugin));
 }
@@ -1,30 +1,74 @@
-use std::f32::consts::PI;
+use std::{f32::consts::PI, time::Duration};
 
 use bevy::{math::VectorSpace, prelude::*};
 use bevy_aseprite_ultra::prelude::*;
 
 use crate::{
     game::{
-        assets::handles::AsepriteAssets, camera::CameraTarget, collider::Collider,
+        assets::handles::AsepriteAssets,
+        camera::CameraTarget,
+        circuit::{Circuit, CircuitDuration},
+        collider::Collider,
+        course::CourseWatch,
+        input::PlayerInput,
+        letter::Letters,
         movements::Velocity,
+        rival::{Rival, RivalSkill, RIVAL_COLOR, RIVAL_SLOT},
     },
     screen::Screen,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.add_event::<SpawnPlayer>();
-    app.register_type::<(Player, PlayerController)>();
+    app.register_type::<(Player, PlayerSlot, PlayerController, Health)>();
     app.observe(spawn_player);
 }
 
-// NOTE - to trigger this event : `commands.trigger(SpawnPlayer);`
+// NOTE - to trigger this event : `commands.trigger(SpawnPlayer::new(position, circuit, letters));`
 #[derive(Event, Debug)]
-pub struct SpawnPlayer(pub Vec2);
+pub struct SpawnPlayer {
+    pub position: Vec2,
+    pub slot: usize,
+    /// Track of the map, each player rides it on their own
+    pub circuit: Circuit,
+    /// Letters to deliver, each player has a full bag
+    pub letters: Letters,
+    /// Ridden by the computer instead of the keyboard
+    pub rival: Option<RivalSkill>,
+}
+
+impl SpawnPlayer {
+    pub fn new(position: Vec2, circuit: Circuit, letters: Letters) -> Self {
+        Self {
+            position,
+            slot: 0,
+            circuit,
+            letters,
+            rival: None,
+        }
+    }
+
+    pub fn with_slot(mut self, slot: usize) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    pub fn with_rival(mut self, skill: RivalSkill) -> Self {
+        self.slot = RIVAL_SLOT;
+        self.rival = Some(skill);
+        self
+    }
+}
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
+/// Seat of a local player, 0 for the first one, it picks the keys and the camera
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct PlayerSlot(pub usize);
+
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct PlayerParticles;
@@ -39,8 +83,12 @@ pub struct PlayerController {
     pub actual_collision: Option<Collider>,
     pub actual_chunk: Option<Collider>,
     pub damn: bool,
+    /// Obstacle the player is still riding over, so it only acts once
+    pub last_obstacle: Option<Entity>,
     pub start_timer: Timer,
     pub obstacle_timer: Timer,
+    /// Time the deliver key has to be held at a parcel house
+    pub parcel_timer: Timer,
 }
 
 impl Default for PlayerController {
@@ -53,10 +101,52 @@ impl Default for PlayerController {
             actual_collision: None,
             actual_chunk: None,
             damn: false,
+            last_obstacle: None,
             start_timer: Timer::from_seconds(1., TimerMode::Once),
             obstacle_timer: Timer::from_seconds(1.5, TimerMode::Once),
+            parcel_timer: Timer::from_seconds(1., TimerMode::Once),
+        }
+    }
+}
+
+pub const PLAYER_HEALTH: i32 = 5;
+
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+    /// Number of hits taken during the run
+    pub hits: i32,
+    /// Short time after a hit where the player can't be hurt
+    pub invincible_timer: Timer,
+}
+
+impl Health {
+    pub fn new(max: i32) -> Self {
+        let mut invincible_timer = Timer::from_seconds(1., TimerMode::Once);
+        invincible_timer.tick(Duration::from_secs(1));
+
+        Self {
+            current: max,
+            max,
+            hits: 0,
+            invincible_timer,
         }
     }
+
+    pub fn hit(&mut self, damage: i32) {
+        self.current = (self.current - damage).max(0);
+        self.hits += 1;
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+
+    pub fn can_be_hurt(&self) -> bool {
+        self.invincible_timer.finished()
+    }
 }
 
 #[derive(Component, Reflect)]
@@ -68,6 +158,15 @@ pub struct PlayerMovement {
     pub max_speed: f32,
     pub friction: f32,
     pub dash_mul: f32,
+    /// How fast the sideways part of the velocity is absorbed by the tyres
+    pub grip: f32,
+    /// Grip used while braking in a turn
+    pub drift_grip: f32,
+    pub drift_turn_speed: f32,
+    pub drift_min_speed: f32,
+    pub drifting: bool,
+    /// Sideways speed of the last tick, used for skid marks
+    pub slip: f32,
 }
 
 impl Default for PlayerMovement {
@@ -81,6 +180,12 @@ impl Default for PlayerMovement {
             friction: 2.,
             // friction: 2.25 * MULT,
             dash_mul: 2.,
+            grip: 12.,
+            drift_grip: 1.5,
+            drift_turn_speed: 8.,
+            drift_min_speed: 0.4 * MULT,
+            drifting: false,
+            slip: 0.,
         }
     }
 }
@@ -90,27 +195,46 @@ fn spawn_player(
     mut commands: Commands,
     aseprite_handles: Res<AsepriteAssets>,
 ) {
-    println!("Spawning player {:?}", trigger.event().0);
+    let event = trigger.event();
     let mut entity = commands.spawn((
-        Name::new("Player"),
+        Name::new(format!("Player {}", event.slot + 1)),
         //
         AsepriteAnimationBundle {
             aseprite: aseprite_handles.get("postman"),
             animation: Animation::default().with_tag("ride"),
-            transform: Transform::from_translation(trigger.event().0.extend(0.1))
+            transform: Transform::from_translation(event.position.extend(0.1))
                 .with_rotation(Quat::from_axis_angle(Vec3::Z, -PI / 2.))
                 .with_scale(Vec3::new(1.5, 1.5, 0.)),
+            sprite: Sprite {
+                color: event.rival.map_or(Color::WHITE, |_| RIVAL_COLOR),
+                ..default()
+            },
             ..default()
         },
         //
         Player,
+        PlayerSlot(event.slot),
         PlayerController::default(),
         PlayerMovement::default(),
+        Health::new(PLAYER_HEALTH),
         Velocity::default(),
-        Collider::new_rect(trigger.event().0, Vec2::splat(3.)),
+        Collider::new_rect(event.position, Vec2::splat(3.)),
+        // state of the run, each player has their own
+        (
+            PlayerInput::default(),
+            event.circuit.clone(),
+            CircuitDuration::default(),
+            CourseWatch::default(),
+            event.letters.clone(),
+        ),
         //
         CameraTarget,
         //
         StateScoped(Screen::Playing),
     ));
+
+    if let Some(skill) = event.rival {
+        entity.insert((Name::new("Rival"), Rival::new(skill)));
+        entity.remove::<CameraTarget>();
+    }
 }
@@ -0,0 +1,200 @@
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::screen::Screen;
+
+use super::{
+    input::{read_gamepads, Action, InputSet, PlayerInput},
+    replay::ReplayPlayback,
+    rival::Rival,
+    spawn::player::{Player, PlayerSlot},
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(TouchControls, TouchButton)>();
+    app.add_systems(
+        Update,
+        (
+            detect_touch.run_if(not(resource_exists::<TouchControls>)),
+            (spawn_touch_overlay, update_touch_buttons).chain().run_if(
+                in_state(Screen::Playing)
+                    .and_then(in_state(GameState::Run))
+                    .and_then(resource_exists::<TouchControls>),
+            ),
+        ),
+    );
+    app.add_systems(OnEnter(GameState::End), despawn_touch_overlay);
+    app.add_systems(
+        FixedUpdate,
+        read_touch_buttons
+            .after(read_gamepads)
+            .in_set(InputSet)
+            .run_if(in_state(Screen::Playing).and_then(not(resource_exists::<ReplayPlayback>))),
+    );
+}
+
+/// A finger touched the screen, the ride shows its buttons from now on
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct TouchControls;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+struct TouchOverlay;
+
+/// On-screen button of the first player, held while a finger is on it
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct TouchButton {
+    pub action: Action,
+    pub held: bool,
+}
+
+const TOUCH_BUTTON_SIZE: f32 = 90.;
+const TOUCH_BUTTON_COLOR: Color = Color::srgba(1., 1., 1., 0.25);
+const TOUCH_BUTTON_HELD_COLOR: Color = Color::srgba(1., 1., 1., 0.6);
+
+fn detect_touch(mut commands: Commands, touches: Res<Touches>) {
+    if touches.any_just_pressed() {
+        commands.init_resource::<TouchControls>();
+    }
+}
+
+/// Steering under the left thumb, the pedals and the letters under the right one
+fn spawn_touch_overlay(mut commands: Commands, overlay_query: Query<(), With<TouchOverlay>>) {
+    if !overlay_query.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            Name::new("Touch Overlay"),
+            NodeBundle {
+                style: Style {
+                    width: Percent(100.),
+                    height: Percent(100.),
+                    padding: UiRect::all(Px(20.)),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexEnd,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            TouchOverlay,
+            StateScoped(Screen::Playing),
+        ))
+        .with_children(|children| {
+            for group in [
+                &[(Action::SteerLeft, "<"), (Action::SteerRight, ">")][..],
+                &[
+                    (Action::Throw, "Throw"),
+                    (Action::Brake, "Brake"),
+                    (Action::Accelerate, "Gas"),
+                ][..],
+            ] {
+                children
+                    .spawn(NodeBundle {
+                        style: Style {
+                            column_gap: Px(15.),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|children| {
+                        for (action, text) in group {
+                            spawn_touch_button(children, *action, text);
+                        }
+                    });
+            }
+        });
+}
+
+fn spawn_touch_button(children: &mut ChildBuilder, action: Action, text: &str) {
+    children
+        .spawn((
+            Name::new(format!("Touch {}", action.name())),
+            NodeBundle {
+                style: Style {
+                    width: Px(TOUCH_BUTTON_SIZE),
+                    height: Px(TOUCH_BUTTON_SIZE),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(TOUCH_BUTTON_COLOR),
+                border_radius: BorderRadius::all(Px(TOUCH_BUTTON_SIZE / 2.)),
+                ..default()
+            },
+            TouchButton {
+                action,
+                held: false,
+            },
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 28.,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn despawn_touch_overlay(mut commands: Commands, overlay_query: Query<Entity, With<TouchOverlay>>) {
+    for entity in overlay_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Each finger holds the button under it, several buttons can be held together
+fn update_touch_buttons(
+    touches: Res<Touches>,
+    mut button_query: Query<(
+        &Node,
+        &GlobalTransform,
+        &mut TouchButton,
+        &mut BackgroundColor,
+    )>,
+) {
+    for (node, transform, mut button, mut background) in button_query.iter_mut() {
+        let rect = node.logical_rect(transform);
+        let held = touches.iter().any(|touch| rect.contains(touch.position()));
+
+        if button.held != held {
+            button.held = held;
+            background.0 = if held {
+                TOUCH_BUTTON_HELD_COLOR
+            } else {
+                TOUCH_BUTTON_COLOR
+            };
+        }
+    }
+}
+
+/// Adds the held buttons to the keys of the first player
+fn read_touch_buttons(
+    button_query: Query<&TouchButton>,
+    mut player_query: Query<(&mut PlayerInput, &PlayerSlot), (With<Player>, Without<Rival>)>,
+) {
+    let Some((mut input, _)) = player_query.iter_mut().find(|(_, slot)| slot.0 == 0) else {
+        return;
+    };
+
+    for button in button_query.iter().filter(|button| button.held) {
+        match button.action {
+            Action::Accelerate => input.forward = true,
+            Action::Brake => input.brake = true,
+            Action::SteerLeft => input.left = true,
+            Action::SteerRight => input.right = true,
+            // one thumb for the letter box: throw, hand a parcel or take the mail
+            Action::Throw => {
+                input.launch = true;
+                input.deliver = true;
+                input.collect = true;
+            }
+            _ => {}
+        }
+    }
+}
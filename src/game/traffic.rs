@@ -0,0 +1,98 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::screen::Screen;
+
+use super::{
+    map::{chunk::ChunkConnextion, road::RoadNetwork},
+    movements::Velocity,
+    rng::GameRng,
+    spawn::car::Car,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        drive_cars.run_if(in_state(Screen::Playing).and_then(resource_exists::<RoadNetwork>)),
+    );
+}
+
+/// Distance between the middle of the road and a lane
+const LANE_OFFSET: f32 = 20.;
+const CAR_ACCELERATION: f32 = 2.;
+const CAR_SAFE_DISTANCE: f32 = 26.;
+const CAR_LOOK_AHEAD: f32 = 50.;
+
+/// Where a car heading to `heading` drives in the cell, cars keep to the right
+pub fn lane_target(cell: IVec2, heading: ChunkConnextion) -> Vec2 {
+    let direction = heading.to_vec2();
+    let right = Vec2::new(direction.y, -direction.x);
+    RoadNetwork::center(cell) + right * LANE_OFFSET
+}
+
+fn drive_cars(
+    time: Res<Time>,
+    road_network: Res<RoadNetwork>,
+    mut game_rng: ResMut<GameRng>,
+    mut query: Query<(Entity, &mut Transform, &mut Velocity, &mut Car)>,
+) {
+    let rng = &mut game_rng.rng;
+
+    let others: Vec<(Entity, Vec2, f32)> = query
+        .iter()
+        .map(|(entity, transform, _, car)| (entity, transform.translation.xy(), car.speed))
+        .collect();
+
+    for (entity, mut transform, mut velocity, mut car) in query.iter_mut() {
+        let position = transform.translation.xy();
+
+        // next chunk reached, choose where to go
+        if position.distance(car.target) <= car.speed.max(1.) + 1. {
+            let offset = car.heading.grid_offset();
+            car.cell += offset;
+
+            let exits = road_network.exits(car.cell, car.heading);
+            car.heading = match exits.choose(rng) {
+                Some(exit) => *exit,
+                None => car.heading.opposite(),
+            };
+            car.target = lane_target(car.cell + car.heading.grid_offset(), car.heading);
+        }
+
+        let direction = (car.target - position).normalize_or_zero();
+
+        // slow down behind other cars
+        let mut wanted_speed = car.max_speed;
+        for (other, other_position, other_speed) in others.iter() {
+            if *other == entity {
+                continue;
+            }
+
+            let relative = *other_position - position;
+            let ahead = relative.dot(direction);
+            if ahead > 0. && ahead < CAR_LOOK_AHEAD && relative.perp_dot(direction).abs() < 10. {
+                let free = ((ahead - CAR_SAFE_DISTANCE) / (CAR_LOOK_AHEAD - CAR_SAFE_DISTANCE))
+                    .clamp(0., 1.);
+                wanted_speed = wanted_speed.min(other_speed + car.max_speed * free);
+            }
+        }
+
+        let step = CAR_ACCELERATION * time.delta_seconds();
+        car.speed = if car.speed < wanted_speed {
+            (car.speed + step).min(wanted_speed)
+        } else {
+            (car.speed - step * 3.).max(wanted_speed)
+        };
+
+        velocity.0 = direction * car.speed;
+
+        if direction != Vec2::ZERO {
+            transform.rotation = transform.rotation.slerp(
+                Quat::from_rotation_z(direction.to_angle() - PI / 2.),
+                (time.delta_seconds() * 8.).min(1.),
+            );
+        }
+    }
+}
@@ -9,20 +9,41 @@ use crate::ui::prelude::*;
 use crate::{screen::Screen, ui::prelude::Containers};
 
 use super::assets::handles::{AsepriteAssets, FontAssets};
-use super::circuit::CircuitDuration;
+use super::camera::{MainCamera, PlayerCamera};
+use super::circuit::{Circuit, CircuitDuration};
+use super::daily::DailyRoute;
+use super::endless::EndlessMode;
+use super::lan::LanRace;
 use super::letter::{LetterUi, Letters};
+use super::replay::{Replay, ReplayPlayback, StopReplay};
 use super::restart::Restart;
-use super::save::GameSave;
+use super::rival::Rival;
+use super::save::{GameSave, RecordResult};
+use super::score::ScoreModel;
+use super::spawn::player::{Health, Player, PlayerSlot};
+use super::versus::is_race;
 use super::GameState;
 
 pub(super) fn plugin(app: &mut App) {
+    app.register_type::<HudOf>();
     app.add_systems(OnEnter(Screen::Playing), spawn_ui);
-    app.add_systems(OnEnter(GameState::EndScreen), spawn_end_ui);
+    app.add_systems(
+        OnEnter(GameState::EndScreen),
+        spawn_end_ui.run_if(
+            not(resource_exists::<EndlessMode>)
+                .and_then(not(is_race))
+                .and_then(not(resource_exists::<LanRace>)),
+        ),
+    );
 
     app.add_systems(
         Update,
         (
+            spawn_player_hud,
             update_circuit_duration_text,
+            update_lap_text,
+            update_delivery_text,
+            update_health_ui,
             handle_end_action.run_if(in_state(GameState::EndScreen)),
         )
             .run_if(in_state(Screen::Playing)),
@@ -35,8 +56,9 @@ pub(super) fn plugin(app: &mut App) {
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
-enum EndAction {
+pub enum EndAction {
     Restart,
+    Replay,
     Next,
     Menu,
 }
@@ -85,65 +107,35 @@ pub struct InfoTextContainer {
     pub current_pos: Vec2,
 }
 
+/// HUD element showing the state of a player
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct HudOf(pub Entity);
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Reflect, Default)]
 #[reflect(Component)]
 pub struct CircuitDurationText;
 
-pub fn spawn_ui(mut commands: Commands, fonts: Res<FontAssets>, current_level: Res<CurrentLevel>) {
-    commands.spawn((
-        Name::new("Letter UI Root"),
-        NodeBundle {
-            style: Style {
-                margin: UiRect::all(Px(20.)),
-                width: Auto,
-                height: Auto,
-                justify_content: JustifyContent::FlexStart,
-                align_items: AlignItems::FlexStart,
-                flex_direction: FlexDirection::Row,
-                column_gap: Px(-15.),
-                position_type: PositionType::Absolute,
-                ..default()
-            },
-            ..default()
-        },
-        StateScoped(Screen::Playing),
-        LetterUi::default(),
-    ));
+/// Delivered, missed and pending houses
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect, Default)]
+#[reflect(Component)]
+pub struct DeliveryText;
 
-    // Circuit duration
-    commands
-        .spawn((
-            Name::new("Circuit Duration Container"),
-            NodeBundle {
-                style: Style {
-                    width: Percent(50.),
-                    top: Px(15.),
-                    right: Px(20.),
-                    justify_content: JustifyContent::FlexEnd,
-                    position_type: PositionType::Absolute,
-                    ..default()
-                },
-                // background_color: BackgroundColor(Color::srgba(1., 1., 1., 0.8)),
-                // border_radius: BorderRadius::all(Px(5.)),
-                ..default()
-            },
-            StateScoped(Screen::Playing),
-        ))
-        .with_children(|children| {
-            children.spawn((
-                Name::new("Circuit Duration Text"),
-                TextBundle::from_sections(vec![TextSection {
-                    value: "0.0".into(),
-                    style: TextStyle {
-                        font_size: 42.,
-                        color: Color::Srgba(WHITE),
-                        font: fonts.get("gamer"),
-                    },
-                }]),
-                CircuitDurationText,
-            ));
-        });
+/// Current lap and the split of each finished lap
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect, Default)]
+#[reflect(Component)]
+pub struct LapText;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect, Default)]
+#[reflect(Component)]
+pub struct HealthUi {
+    show: Option<i32>,
+}
 
+const HEALTH_FILL: Color = Color::srgb(0.85, 0.2, 0.25);
+const HEALTH_EMPTY: Color = Color::srgba(1., 1., 1., 0.4);
+
+pub fn spawn_ui(mut commands: Commands) {
     // Info text
     commands
         .ui_root(RootAnchor::BottomCenter)
@@ -180,13 +172,163 @@ pub fn spawn_ui(mut commands: Commands, fonts: Res<FontAssets>, current_level: R
         });
 }
 
+/// Each player gets its letters, health and timer, drawn over its own camera
+fn spawn_player_hud(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    player_query: Query<(Entity, &PlayerSlot), (Added<Player>, Without<Rival>)>,
+    camera_query: Query<
+        (Entity, Option<&PlayerCamera>),
+        Or<(With<MainCamera>, With<PlayerCamera>)>,
+    >,
+) {
+    for (player, slot) in player_query.iter() {
+        let camera = camera_query
+            .iter()
+            .find(|(_, player_camera)| player_camera.map_or(0, |camera| camera.0) == slot.0)
+            .map(|(camera, _)| camera);
+
+        for entity in spawn_hud(&mut commands, &fonts, player) {
+            commands
+                .entity(entity)
+                .insert((HudOf(player), StateScoped(Screen::Playing)));
+            if let Some(camera) = camera {
+                commands.entity(entity).insert(TargetCamera(camera));
+            }
+        }
+    }
+}
+
+/// Root nodes of the HUD of a player
+fn spawn_hud(commands: &mut Commands, fonts: &FontAssets, player: Entity) -> [Entity; 4] {
+    let letter_ui = commands
+        .spawn((
+            Name::new("Letter UI Root"),
+            NodeBundle {
+                style: Style {
+                    margin: UiRect::all(Px(20.)),
+                    width: Auto,
+                    height: Auto,
+                    justify_content: JustifyContent::FlexStart,
+                    align_items: AlignItems::FlexStart,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Px(4.),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            LetterUi::default(),
+        ))
+        .id();
+
+    // Health
+    let health_ui = commands
+        .spawn((
+            Name::new("Health UI Root"),
+            NodeBundle {
+                style: Style {
+                    top: Px(70.),
+                    left: Px(20.),
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Px(6.),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            HealthUi::default(),
+        ))
+        .id();
+
+    // Deliveries
+    let delivery_text = commands
+        .spawn((
+            Name::new("Delivery Text"),
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.,
+                    color: Color::Srgba(WHITE),
+                    font: fonts.get("gamer"),
+                },
+            )
+            .with_style(Style {
+                top: Px(100.),
+                left: Px(20.),
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+            DeliveryText,
+        ))
+        .id();
+
+    // Circuit duration
+    let circuit_duration = commands
+        .spawn((
+            Name::new("Circuit Duration Container"),
+            NodeBundle {
+                style: Style {
+                    width: Percent(50.),
+                    top: Px(15.),
+                    right: Px(20.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                // background_color: BackgroundColor(Color::srgba(1., 1., 1., 0.8)),
+                // border_radius: BorderRadius::all(Px(5.)),
+                ..default()
+            },
+        ))
+        .with_children(|children| {
+            children.spawn((
+                Name::new("Circuit Duration Text"),
+                TextBundle::from_sections(vec![TextSection {
+                    value: "0.0".into(),
+                    style: TextStyle {
+                        font_size: 42.,
+                        color: Color::Srgba(WHITE),
+                        font: fonts.get("gamer"),
+                    },
+                }]),
+                CircuitDurationText,
+                HudOf(player),
+            ));
+            children.spawn((
+                Name::new("Lap Text"),
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        color: Color::Srgba(WHITE),
+                        font: fonts.get("gamer"),
+                    },
+                )
+                .with_text_justify(JustifyText::Right),
+                LapText,
+                HudOf(player),
+            ));
+        })
+        .id();
+
+    [letter_ui, health_ui, delivery_text, circuit_duration]
+}
+
 fn spawn_end_ui(
     mut commands: Commands,
-    letters: Res<Letters>,
     current_level: Res<CurrentLevel>,
     game_save: Res<GameSave>,
     aseprites: Res<AsepriteAssets>,
+    record_result: Option<Res<RecordResult>>,
+    score_model: Res<ScoreModel>,
+    player_query: Query<(&Letters, &CircuitDuration, &Health), With<Player>>,
+    daily: Option<Res<DailyRoute>>,
 ) {
+    let Ok((letters, circuit_duration, health)) = player_query.get_single() else {
+        return;
+    };
     commands.trigger(PlaySoundtrack::Disable);
 
     println!("End UI");
@@ -197,16 +339,71 @@ fn spawn_end_ui(
             BackgroundColor(BACKGROUND.with_alpha(0.2)),
         ))
         .with_children(|children| {
-            let letters_lost = letters.all - letters.to_post;
-            let (stars, message) = if letters.to_post == 0 {
-                (3, "Good job !")
-            } else if letters_lost > letters.all / 2 {
-                (2, "Not bad")
-            } else {
-                (1, "Not insane")
+            let hits = health.hits;
+            let score = score_model.score(letters, circuit_duration.total, hits);
+            let stars = score.stars;
+            let message = match stars {
+                3 => "Good job !",
+                2 => "Not bad",
+                1 => "Not insane",
+                _ => "Keep trying",
             };
 
             children.heading(message, HeadingSize::H3);
+            if let Some(route) = &daily {
+                children.label(format!("Daily Route {}", route.date_string()));
+            }
+            children.label(format!(
+                "Score: {} - Par: {:.0}s",
+                score.points, score_model.par
+            ));
+            children.label(format!(
+                "Delivered: {} - Missed: {} - Pending: {}",
+                letters.delivered(),
+                letters.lost,
+                letters.pending()
+            ));
+            if letters.parcels > 0 {
+                children.label(format!("Parcels: {}", letters.parcels));
+            }
+            if letters.wrong > 0 {
+                children.label(format!("Wrong address: {}", letters.wrong));
+            }
+            if letters.mail > 0 {
+                children.label(format!(
+                    "Outgoing mail: {}/{}",
+                    letters.mail_returned, letters.mail
+                ));
+            }
+
+            if let Some(result) = &record_result {
+                if result.new_record {
+                    children.label(format!("{:.2} - New record!", circuit_duration.total));
+                } else {
+                    children.label(format!("{:.2}", circuit_duration.total));
+                }
+                if let Some(previous) = &result.previous_best {
+                    children.label(format!(
+                        "Previous best {}: {:.2} ({})",
+                        previous.direction.name(),
+                        previous.time,
+                        previous.date_string()
+                    ));
+                }
+            }
+
+            children.label(format!("Hits taken: {}", hits));
+
+            if let Some(best_lap) = circuit_duration.best_lap() {
+                let previous = record_result
+                    .as_ref()
+                    .and_then(|result| result.previous_best_lap);
+                if previous.is_none_or(|previous| best_lap < previous) {
+                    children.label(format!("Best lap: {:.2} - New record!", best_lap));
+                } else {
+                    children.label(format!("Best lap: {:.2}", best_lap));
+                }
+            }
 
             children
                 .spawn((
@@ -244,10 +441,16 @@ fn spawn_end_ui(
             children
                 .button_sprite("Restart", aseprites.get("button"), None)
                 .insert(EndAction::Restart);
-            if current_level.indice < game_save.levels.len() as i32 - 1 {
+            // the daily route keeps no replay and has no next level
+            if daily.is_none() {
                 children
-                    .button_sprite("Next", aseprites.get("button"), None)
-                    .insert(EndAction::Next);
+                    .button_sprite("Replay", aseprites.get("button"), None)
+                    .insert(EndAction::Replay);
+                if current_level.indice < game_save.levels.len() as i32 - 1 {
+                    children
+                        .button_sprite("Next", aseprites.get("button"), None)
+                        .insert(EndAction::Next);
+                }
             }
             children
                 .button_sprite("Menu", aseprites.get("button"), None)
@@ -261,17 +464,33 @@ fn handle_end_action(
     mut button_query: InteractionQuery<&EndAction>,
     mut current_level: ResMut<CurrentLevel>,
     game_save: Res<GameSave>,
+    player_query: Query<&Circuit, With<Player>>,
 ) {
     for (interaction, action) in button_query.iter_mut() {
         if matches!(interaction, Interaction::Pressed) {
             match action {
                 EndAction::Next => {
+                    commands.trigger(StopReplay);
                     commands.trigger(Restart);
                     current_level.indice += 1;
                 }
                 EndAction::Restart => {
                     commands.trigger(Restart);
                 }
+                EndAction::Replay => {
+                    // the run just ridden, in its direction
+                    let direction = player_query
+                        .get_single()
+                        .map_or(default(), |circuit| circuit.direction);
+                    let replay = game_save
+                        .level_name(current_level.indice)
+                        .and_then(|level_name| Replay::load(level_name, direction));
+                    if let Some(replay) = replay {
+                        current_level.indice = replay.level;
+                        commands.insert_resource(ReplayPlayback::new(replay));
+                        commands.trigger(Restart);
+                    }
+                }
                 EndAction::Menu => {
                     commands.trigger(PlaySoundtrack::Key("ChillMenu".into()));
                     next_screen.set(Screen::Title);
@@ -311,10 +530,101 @@ pub fn update_info_text(
 }
 
 pub fn update_circuit_duration_text(
-    mut circuit_duration: ResMut<CircuitDuration>,
-    mut text_query: Query<&mut Text, With<CircuitDurationText>>,
+    player_query: Query<&CircuitDuration>,
+    mut text_query: Query<(&mut Text, &HudOf), With<CircuitDurationText>>,
+) {
+    for (mut text, hud) in text_query.iter_mut() {
+        if let Ok(circuit_duration) = player_query.get(hud.0) {
+            text.sections[0].value = format!("{:.2}", circuit_duration.total);
+        }
+    }
+}
+
+pub fn update_delivery_text(
+    player_query: Query<Ref<Letters>>,
+    mut text_query: Query<(&mut Text, &HudOf), With<DeliveryText>>,
+) {
+    for (mut text, hud) in text_query.iter_mut() {
+        let Ok(letters) = player_query.get(hud.0) else {
+            continue;
+        };
+        if !letters.is_changed() {
+            continue;
+        }
+
+        text.sections[0].value = format!(
+            "Delivered {} - Missed {} - Pending {}",
+            letters.delivered(),
+            letters.lost,
+            letters.pending()
+        );
+        if letters.taken > 0 {
+            text.sections[0].value += &format!(" - Taken {}", letters.taken);
+        }
+        if letters.mail_carried > 0 {
+            text.sections[0].value += &format!(" - Mail on board {}", letters.mail_carried);
+        }
+    }
+}
+
+pub fn update_lap_text(
+    player_query: Query<(&Circuit, &CircuitDuration)>,
+    mut text_query: Query<(&mut Text, &HudOf), With<LapText>>,
 ) {
-    if let Ok(mut text) = text_query.get_single_mut() {
-        text.sections[0].value = format!("{:.2}", circuit_duration.0);
+    for (mut text, hud) in text_query.iter_mut() {
+        let Ok((circuit, circuit_duration)) = player_query.get(hud.0) else {
+            continue;
+        };
+        if circuit.laps <= 1 {
+            continue;
+        }
+
+        let mut value = format!(
+            "Lap {}/{}",
+            (circuit.lap + 1).min(circuit.laps),
+            circuit.laps
+        );
+        for (i, lap) in circuit_duration.laps.iter().enumerate() {
+            value.push_str(&format!("\n{}: {:.2}", i + 1, lap));
+        }
+        text.sections[0].value = value;
+    }
+}
+
+pub fn update_health_ui(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut HealthUi, &HudOf)>,
+    player_query: Query<&Health, With<Player>>,
+) {
+    for (entity, mut health_ui, hud) in query.iter_mut() {
+        let Ok(health) = player_query.get(hud.0) else {
+            continue;
+        };
+
+        if health_ui.show != Some(health.current) {
+            commands.entity(entity).despawn_descendants();
+
+            commands.entity(entity).with_children(|children| {
+                for i in 0..health.max {
+                    let color = if i < health.current {
+                        HEALTH_FILL
+                    } else {
+                        HEALTH_EMPTY
+                    };
+                    children.spawn(NodeBundle {
+                        style: Style {
+                            width: Px(18.),
+                            height: Px(18.),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(color),
+                        border_radius: BorderRadius::all(Px(4.)),
+                        ..default()
+                    });
+                }
+            });
+
+            health_ui.show = Some(health.current);
+        }
     }
 }
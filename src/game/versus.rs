@@ -0,0 +1,212 @@
+use bevy::{
+    prelude::*,
+    render::{camera::Viewport, view::RenderLayers},
+    window::PrimaryWindow,
+};
+
+use crate::{
+    screen::Screen,
+    ui::{palette::BACKGROUND, prelude::*},
+};
+
+use super::{
+    assets::handles::AsepriteAssets,
+    audio::soundtrack::PlaySoundtrack,
+    camera::{MainCamera, PlayerCamera},
+    circuit::{Circuit, CircuitDuration},
+    letter::Letters,
+    rival::RivalRace,
+    score::ScoreModel,
+    spawn::player::{Health, Player, PlayerSlot},
+    ui::EndAction,
+    GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(VersusMode, VersusUiCamera)>();
+    app.add_systems(OnEnter(Screen::Title), leave_versus_mode);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        spawn_versus_cameras.run_if(resource_exists::<VersusMode>),
+    );
+    app.add_systems(OnExit(Screen::Playing), reset_main_viewport);
+    app.add_systems(
+        Update,
+        split_viewports.run_if(in_state(Screen::Playing).and_then(resource_exists::<VersusMode>)),
+    );
+    app.add_systems(
+        OnEnter(GameState::EndScreen),
+        spawn_race_end_ui.run_if(is_race),
+    );
+}
+
+/// Several postmen ride the map, the end screen ranks them
+pub fn is_race(versus: Option<Res<VersusMode>>, rival: Option<Res<RivalRace>>) -> bool {
+    versus.is_some() || rival.is_some()
+}
+
+/// Two postmen race on the same map, each on one half of the window
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Resource)]
+pub struct VersusMode;
+
+/// Draws the shared UI over both halves, it sees no sprite of the map
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct VersusUiCamera;
+
+/// The second postman starts next to the first one, further in the start chunk
+pub const VERSUS_START_OFFSET: Vec2 = Vec2::new(16., 0.);
+/// Nothing of the map is drawn on this layer
+const UI_LAYER: usize = 1;
+
+fn leave_versus_mode(mut commands: Commands) {
+    commands.remove_resource::<VersusMode>();
+}
+
+fn spawn_versus_cameras(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Player 2 Camera"),
+        Camera2dBundle {
+            camera: Camera {
+                order: 1,
+                // the main camera already cleared the window
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            ..default()
+        },
+        PlayerCamera(1),
+        StateScoped(Screen::Playing),
+    ));
+
+    commands.spawn((
+        Name::new("Versus UI Camera"),
+        Camera2dBundle {
+            camera: Camera {
+                order: 2,
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(UI_LAYER),
+        IsDefaultUiCamera,
+        VersusUiCamera,
+        StateScoped(Screen::Playing),
+    ));
+}
+
+/// Side by side halves, following the size of the window
+fn split_viewports(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<
+        (&mut Camera, Option<&PlayerCamera>),
+        Or<(With<MainCamera>, With<PlayerCamera>)>,
+    >,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let size = UVec2::new(window.physical_width() / 2, window.physical_height());
+    if size.x == 0 || size.y == 0 {
+        return;
+    }
+
+    for (mut camera, player_camera) in camera_query.iter_mut() {
+        let slot = player_camera.map_or(0, |camera| camera.0) as u32;
+        let viewport = Viewport {
+            physical_position: UVec2::new(size.x * slot, 0),
+            physical_size: size,
+            ..default()
+        };
+
+        if camera
+            .viewport
+            .as_ref()
+            .map(|current| (current.physical_position, current.physical_size))
+            != Some((viewport.physical_position, viewport.physical_size))
+        {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+fn reset_main_viewport(mut camera_query: Query<&mut Camera, With<MainCamera>>) {
+    for mut camera in camera_query.iter_mut() {
+        if camera.viewport.is_some() {
+            camera.viewport = None;
+        }
+    }
+}
+
+/// The race stops at the first finish, the best score wins and a tie goes to the finisher
+fn spawn_race_end_ui(
+    mut commands: Commands,
+    aseprites: Res<AsepriteAssets>,
+    score_model: Res<ScoreModel>,
+    player_query: Query<
+        (
+            &Name,
+            &PlayerSlot,
+            &Circuit,
+            &CircuitDuration,
+            &Letters,
+            &Health,
+        ),
+        With<Player>,
+    >,
+) {
+    commands.trigger(PlaySoundtrack::Disable);
+
+    let mut results: Vec<_> = player_query
+        .iter()
+        .map(|(name, slot, circuit, circuit_duration, letters, health)| {
+            let score = score_model.score(letters, circuit_duration.total, health.hits);
+            let finished = circuit.finished() && letters.mail_carried == 0;
+            (
+                slot.0,
+                name.to_string(),
+                score.points,
+                finished,
+                circuit_duration.total,
+                letters.clone(),
+            )
+        })
+        .collect();
+    results.sort_by_key(|(slot, _, points, finished, ..)| (-points, !finished, *slot));
+
+    commands
+        .ui_root(RootAnchor::Center)
+        .insert((
+            StateScoped(Screen::Playing),
+            BackgroundColor(BACKGROUND.with_alpha(0.2)),
+        ))
+        .with_children(|children| {
+            if let Some((_, name, ..)) = results.first() {
+                children.heading(format!("{} wins!", name), HeadingSize::H3);
+            }
+
+            for (_, name, points, finished, time, letters) in results.iter() {
+                let time = if *finished {
+                    format!("{:.2}", time)
+                } else {
+                    "Not finished".into()
+                };
+                children.label(format!("{}: {} - {}", name, points, time));
+                children.label(format!(
+                    "Delivered: {} - Missed: {} - Taken: {}",
+                    letters.delivered(),
+                    letters.lost,
+                    letters.taken
+                ));
+            }
+
+            children
+                .button_sprite("Restart", aseprites.get("button"), None)
+                .insert(EndAction::Restart);
+            children
+                .button_sprite("Menu", aseprites.get("button"), None)
+                .insert(EndAction::Menu);
+        });
+}
@@ -0,0 +1,174 @@
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    game::{
+        assets::handles::AsepriteAssets,
+        input::{key_name, Action, KeyBindings},
+        save::GameSave,
+    },
+    ui::{palette::LABEL_TEXT, prelude::*},
+};
+
+use super::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(ControlsAction, Rebinding)>();
+    app.add_systems(OnEnter(Screen::Controls), enter_controls);
+    app.add_systems(OnExit(Screen::Controls), exit_controls);
+    app.add_systems(
+        Update,
+        (handle_controls_action, capture_key, update_binding_text)
+            .chain()
+            .run_if(in_state(Screen::Controls)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum ControlsAction {
+    /// Wait for the next key of a player slot
+    Rebind(usize, Action),
+    Reset,
+    Back,
+}
+
+/// Action waiting for its new key, escape cancels
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+struct Rebinding(Option<(usize, Action)>);
+
+const BINDING_BUTTON_SIZE: Vec2 = Vec2::new(200., 50.);
+
+fn enter_controls(mut commands: Commands, aseprites: Res<AsepriteAssets>) {
+    commands.init_resource::<Rebinding>();
+
+    commands
+        .ui_root(RootAnchor::Center)
+        .insert(StateScoped(Screen::Controls))
+        .with_children(|children| {
+            children.heading("Controls", HeadingSize::H2);
+
+            // an action by row, a player by column
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Grid,
+                        row_gap: Px(6.),
+                        column_gap: Px(20.),
+                        align_items: AlignItems::Center,
+                        grid_template_columns: vec![RepeatedGridTrack::auto(3)],
+                        margin: UiRect::bottom(Px(20.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|children| {
+                    for action in Action::RIDE.into_iter().chain(Action::GLOBAL) {
+                        children.spawn(TextBundle::from_section(
+                            action.name(),
+                            TextStyle {
+                                font_size: 24.,
+                                color: LABEL_TEXT,
+                                ..default()
+                            },
+                        ));
+
+                        for slot in 0..KeyBindings::default().slots.len() {
+                            if slot > 0 && Action::GLOBAL.contains(&action) {
+                                children.spawn(NodeBundle::default());
+                                continue;
+                            }
+                            children
+                                .button_sprite(
+                                    "",
+                                    aseprites.get("button"),
+                                    Some(BINDING_BUTTON_SIZE),
+                                )
+                                .insert(ControlsAction::Rebind(slot, action));
+                        }
+                    }
+                });
+
+            children
+                .button_sprite("Reset", aseprites.get("button"), None)
+                .insert(ControlsAction::Reset);
+            children
+                .button_sprite("Back", aseprites.get("button"), None)
+                .insert(ControlsAction::Back);
+        });
+}
+
+fn exit_controls(mut commands: Commands) {
+    commands.remove_resource::<Rebinding>();
+}
+
+fn handle_controls_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    button_query: InteractionQuery<&ControlsAction>,
+    mut rebinding: ResMut<Rebinding>,
+    mut game_save: ResMut<GameSave>,
+) {
+    for (interaction, action) in button_query.iter() {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                ControlsAction::Rebind(slot, action) => rebinding.0 = Some((*slot, *action)),
+                ControlsAction::Reset => {
+                    rebinding.0 = None;
+                    game_save.bindings = KeyBindings::default();
+                    game_save.save();
+                }
+                ControlsAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
+
+fn capture_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut game_save: ResMut<GameSave>,
+) {
+    let Some((slot, action)) = rebinding.0 else {
+        return;
+    };
+    let Some(key) = keys.get_just_pressed().next() else {
+        return;
+    };
+
+    rebinding.0 = None;
+    if *key != KeyCode::Escape {
+        game_save.bindings.bind(slot, action, *key);
+        game_save.save();
+    }
+}
+
+fn update_binding_text(
+    game_save: Res<GameSave>,
+    rebinding: Res<Rebinding>,
+    button_query: Query<(&Children, &ControlsAction)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !game_save.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+
+    for (children, action) in button_query.iter() {
+        let ControlsAction::Rebind(slot, action) = *action else {
+            continue;
+        };
+        let value = if rebinding.0 == Some((slot, action)) {
+            "Press a key".to_string()
+        } else {
+            game_save
+                .bindings
+                .key(slot, action)
+                .map_or("-".into(), key_name)
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value.clone_from(&value);
+            }
+        }
+    }
+}
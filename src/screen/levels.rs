@@ -1,7 +1,12 @@
 use bevy::{prelude::*, ui::Val::*};
 
 use crate::{
-    game::{assets::handles::AsepriteAssets, save::GameSave},
+    game::{
+        assets::handles::AsepriteAssets,
+        circuit::CircuitDirection,
+        rival::{RivalRace, RivalSkill},
+        save::GameSave,
+    },
     ui::prelude::{Containers, DisableButton, InteractionQuery, RootAnchor, Widgets},
 };
 
@@ -11,7 +16,12 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Levels), enter_levels);
     app.add_systems(
         Update,
-        handle_levels_action.run_if(in_state(Screen::Levels)),
+        (
+            handle_levels_action,
+            update_ghost_toggle_text,
+            update_rival_toggle_text,
+        )
+            .run_if(in_state(Screen::Levels)),
     );
 }
 
@@ -19,9 +29,34 @@ pub(super) fn plugin(app: &mut App) {
 #[reflect(Component)]
 enum LevelsAction {
     Play(i32),
+    ToggleGhost,
+    ToggleRival,
     Back,
 }
 
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+struct GhostToggle;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+struct RivalToggle;
+
+fn ghost_toggle_text(game_save: &GameSave) -> &'static str {
+    if game_save.hide_ghost {
+        "Ghost: off"
+    } else {
+        "Ghost: on"
+    }
+}
+
+fn rival_toggle_text(game_save: &GameSave) -> String {
+    match game_save.rival {
+        Some(skill) => format!("Rival: {}", skill.name()),
+        None => "Rival: off".into(),
+    }
+}
+
 fn enter_levels(mut commands: Commands, game_save: Res<GameSave>, aseprites: Res<AsepriteAssets>) {
     commands
         .ui_root(RootAnchor::Center)
@@ -42,10 +77,28 @@ fn enter_levels(mut commands: Commands, game_save: Res<GameSave>, aseprites: Res
                 })
                 .with_children(|children| {
                     for (i, level_data) in game_save.levels.iter().enumerate() {
+                        // the bests of each direction, the ride decides which one counts
+                        let mut text = level_data.name.clone();
+                        if let Some(record) = game_save.record(i as i32) {
+                            for (short, direction) in [
+                                ("CW", CircuitDirection::Clockwise),
+                                ("ACW", CircuitDirection::AntiClockwise),
+                            ] {
+                                if let Some(best) = &record.direction(direction).best {
+                                    text.push_str(&format!(
+                                        "\n{} {:.2} {}/3",
+                                        short,
+                                        best.time,
+                                        record.direction(direction).stars
+                                    ));
+                                }
+                            }
+                        }
+
                         if i as i32 > game_save.last_level_passed {
                             children
                                 .button_sprite(
-                                    level_data.name.clone(),
+                                    text,
                                     aseprites.get("big-button"),
                                     Some(Vec2::splat(250.)),
                                 )
@@ -53,7 +106,7 @@ fn enter_levels(mut commands: Commands, game_save: Res<GameSave>, aseprites: Res
                         } else {
                             children
                                 .button_sprite(
-                                    level_data.name.clone(),
+                                    text,
                                     aseprites.get("big-button"),
                                     Some(Vec2::splat(250.)),
                                 )
@@ -62,6 +115,14 @@ fn enter_levels(mut commands: Commands, game_save: Res<GameSave>, aseprites: Res
                     }
                 });
 
+            children
+                .button_sprite(ghost_toggle_text(&game_save), aseprites.get("button"), None)
+                .insert((LevelsAction::ToggleGhost, GhostToggle));
+
+            children
+                .button_sprite(rival_toggle_text(&game_save), aseprites.get("button"), None)
+                .insert((LevelsAction::ToggleRival, RivalToggle));
+
             // Back button
             children
                 .button_sprite("Back", aseprites.get("button"), None)
@@ -74,16 +135,64 @@ fn handle_levels_action(
     mut next_screen: ResMut<NextState<Screen>>,
     button_query: InteractionQuery<&LevelsAction>,
     mut current_level: ResMut<CurrentLevel>,
+    mut game_save: ResMut<GameSave>,
 ) {
     for (interaction, action) in button_query.iter() {
         if matches!(interaction, Interaction::Pressed) {
             match action {
                 LevelsAction::Play(i) => {
                     current_level.indice = *i;
+                    if let Some(skill) = game_save.rival {
+                        commands.insert_resource(RivalRace(skill));
+                    }
                     next_screen.set(Screen::Playing);
                 }
+                LevelsAction::ToggleGhost => {
+                    game_save.hide_ghost = !game_save.hide_ghost;
+                    game_save.save();
+                }
+                LevelsAction::ToggleRival => {
+                    game_save.rival = RivalSkill::next(game_save.rival);
+                    game_save.save();
+                }
                 LevelsAction::Back => next_screen.set(Screen::Title),
             }
         }
     }
 }
+
+fn update_ghost_toggle_text(
+    game_save: Res<GameSave>,
+    toggle_query: Query<&Children, With<GhostToggle>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !game_save.is_changed() {
+        return;
+    }
+
+    for children in toggle_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = ghost_toggle_text(&game_save).into();
+            }
+        }
+    }
+}
+
+fn update_rival_toggle_text(
+    game_save: Res<GameSave>,
+    toggle_query: Query<&Children, With<RivalToggle>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !game_save.is_changed() {
+        return;
+    }
+
+    for children in toggle_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = rival_toggle_text(&game_save);
+            }
+        }
+    }
+}
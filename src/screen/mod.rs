@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+mod controls;
 mod credits;
 mod levels;
 mod loading;
@@ -18,6 +19,7 @@ pub(super) fn plugin(app: &mut App) {
         credits::plugin,
         playing::plugin,
         levels::plugin,
+        controls::plugin,
     ));
 }
 
@@ -31,5 +33,6 @@ pub enum Screen {
     Credits,
     Playing,
     Levels,
+    Controls,
     Restart, // only for restart the game
 }
@@ -1,13 +1,22 @@
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
     game::{
         audio::soundtrack::PlaySoundtrack,
-        circuit::{Circuit, CircuitDuration, EndCircuitTimer},
-        house::HouseRotate,
-        letter::Letters,
+        camera::MainCamera,
+        circuit::EndCircuitTimer,
+        daily::DailyRoute,
+        endless::{EndlessMode, EndlessRun, SpawnEndless},
+        ghost::GhostRecorder,
+        input::{action_just_pressed, Action},
+        lan::LanRace,
+        map::road::RoadNetwork,
+        replay::{ReplayPlayback, ReplayRecorder},
         restart::RestartCooldown,
+        rng::GameRng,
+        save::RecordResult,
+        score::ScoreModel,
         spawn::{level::SpawnLevel, map::MapTag},
         ui::{spawn_ui, InfoText},
         GameState,
@@ -22,7 +31,7 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         return_to_title_screen
-            .run_if(in_state(Screen::Playing).and_then(input_just_pressed(KeyCode::Escape))),
+            .run_if(in_state(Screen::Playing).and_then(action_just_pressed(Action::Pause))),
     );
 }
 
@@ -36,12 +45,29 @@ pub struct CurrentLevel {
 fn enter_playing(
     mut commands: Commands,
     mut clear_color: ResMut<ClearColor>,
-    mut camera_query: Query<&mut OrthographicProjection, With<Camera>>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
     current_level: Res<CurrentLevel>,
     mut state: ResMut<NextState<GameState>>,
+    playback: Option<Res<ReplayPlayback>>,
+    endless: Option<Res<EndlessMode>>,
+    daily: Option<Res<DailyRoute>>,
+    lan: Option<Res<LanRace>>,
 ) {
     state.set(GameState::Run);
-    commands.trigger(SpawnLevel(current_level.indice));
+
+    // a replay rides the map and traffic it was recorded with, the daily route is seeded by the day
+    // and a LAN race by its host
+    let seed = playback
+        .map(|playback| playback.replay.seed)
+        .or(daily.map(|route| route.seed))
+        .or(lan.map(|race| race.seed))
+        .unwrap_or_else(rand::random);
+    commands.insert_resource(GameRng::new(seed));
+    if endless.is_some() {
+        commands.trigger(SpawnEndless);
+    } else {
+        commands.trigger(SpawnLevel(current_level.indice));
+    }
 
     // set background
     clear_color.0 = BACKGROUND;
@@ -50,11 +76,10 @@ fn enter_playing(
     let mut projection = camera_query.single_mut();
     projection.scale = 0.4;
 
-    commands.init_resource::<Circuit>();
-    commands.init_resource::<HouseRotate>();
     commands.init_resource::<EndCircuitTimer>();
     commands.init_resource::<InfoText>();
-    commands.init_resource::<CircuitDuration>();
+    commands.init_resource::<GhostRecorder>();
+    commands.init_resource::<ReplayRecorder>();
     commands.init_resource::<RestartCooldown>();
 
     commands.trigger(PlaySoundtrack::Key("Go".into()));
@@ -62,20 +87,23 @@ fn enter_playing(
 
 fn exit_playing(
     mut commands: Commands,
-    mut camera_query: Query<&mut OrthographicProjection, With<Camera>>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
 ) {
     // reset camera scale
     let mut projection = camera_query.single_mut();
     projection.scale = 1.;
 
     // remove resources
-    commands.remove_resource::<Circuit>();
-    commands.remove_resource::<HouseRotate>();
-    commands.remove_resource::<Letters>();
     commands.remove_resource::<EndCircuitTimer>();
     commands.remove_resource::<InfoText>();
-    commands.remove_resource::<CircuitDuration>();
+    commands.remove_resource::<GhostRecorder>();
+    commands.remove_resource::<ReplayRecorder>();
+    commands.remove_resource::<GameRng>();
+    commands.remove_resource::<RecordResult>();
+    commands.remove_resource::<ScoreModel>();
     commands.remove_resource::<RestartCooldown>();
+    commands.remove_resource::<RoadNetwork>();
+    commands.remove_resource::<EndlessRun>();
 
     commands.trigger(PlaySoundtrack::Disable);
 }
@@ -5,11 +5,17 @@ use crate::{
     game::{
         assets::handles::{AsepriteAssets, Handles},
         audio::soundtrack::PlaySoundtrack,
+        daily::DailyRoute,
+        endless::EndlessMode,
         save::GameSave,
+        versus::VersusMode,
     },
     ui::prelude::*,
 };
 
+#[cfg(not(target_family = "wasm"))]
+use crate::game::lan::{LanSession, LanStatus};
+
 use super::{playing::CurrentLevel, Screen};
 
 pub(super) fn plugin(app: &mut App) {
@@ -24,6 +30,14 @@ pub(super) fn plugin(app: &mut App) {
 enum TitleAction {
     Play,
     Levels,
+    Endless,
+    Daily,
+    Versus,
+    #[cfg(not(target_family = "wasm"))]
+    HostLan,
+    #[cfg(not(target_family = "wasm"))]
+    JoinLan,
+    Controls,
     Credits,
     #[cfg(not(target_family = "wasm"))]
     Exit,
@@ -50,6 +64,40 @@ fn enter_title(
                 play.insert(TitleAction::Levels);
             }
 
+            children
+                .button_sprite("Endless", aseprite_handles.get("button"), None)
+                .insert(TitleAction::Endless);
+
+            children
+                .button_sprite("Daily Route", aseprite_handles.get("button"), None)
+                .insert(TitleAction::Daily);
+            let today = DailyRoute::today();
+            if let Some(best) = game_save
+                .daily_record(today.day)
+                .and_then(|record| record.best.as_ref())
+            {
+                children.label(format!("Today's best: {:.2}", best.time));
+            }
+
+            children
+                .button_sprite("Versus", aseprite_handles.get("button"), None)
+                .insert(TitleAction::Versus);
+
+            #[cfg(not(target_family = "wasm"))]
+            {
+                children
+                    .button_sprite("Host LAN", aseprite_handles.get("button"), None)
+                    .insert(TitleAction::HostLan);
+                children
+                    .button_sprite("Join LAN", aseprite_handles.get("button"), None)
+                    .insert(TitleAction::JoinLan);
+                children.label("").insert(LanStatus);
+            }
+
+            children
+                .button_sprite("Controls", aseprite_handles.get("button"), None)
+                .insert(TitleAction::Controls);
+
             children
                 .button_sprite("Credits", aseprite_handles.get("button"), None)
                 .insert(TitleAction::Credits);
@@ -69,15 +117,54 @@ fn handle_title_action(
     mut commands: Commands,
     mut next_screen: ResMut<NextState<Screen>>,
     mut button_query: InteractionQuery<&TitleAction>,
+    mut current_level: ResMut<CurrentLevel>,
+    game_save: Res<GameSave>,
     #[cfg(not(target_family = "wasm"))] mut app_exit: EventWriter<AppExit>,
+    #[cfg(not(target_family = "wasm"))] status_query: Query<&Children, With<LanStatus>>,
+    #[cfg(not(target_family = "wasm"))] mut text_query: Query<&mut Text>,
 ) {
     for (interaction, action) in button_query.iter_mut() {
         if matches!(interaction, Interaction::Pressed) {
             match action {
                 TitleAction::Play => {
                     // commands.trigger(PlaySoundtrack::Disable);
+                    current_level.indice = 0;
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Endless => {
+                    commands.insert_resource(EndlessMode);
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Daily => {
+                    let route = DailyRoute::today();
+                    current_level.indice = route.level(game_save.levels.len());
+                    commands.insert_resource(route);
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Versus => {
+                    current_level.indice = 0;
+                    commands.insert_resource(VersusMode);
                     next_screen.set(Screen::Playing);
                 }
+                #[cfg(not(target_family = "wasm"))]
+                TitleAction::HostLan => match LanSession::host() {
+                    Ok(session) => commands.insert_resource(session),
+                    Err(error) => {
+                        warn!("Can't host a LAN race: {}", error);
+                        let status = format!("Can't host: {}", error);
+                        show_lan_status(&status_query, &mut text_query, status);
+                    }
+                },
+                #[cfg(not(target_family = "wasm"))]
+                TitleAction::JoinLan => match LanSession::join() {
+                    Ok(session) => commands.insert_resource(session),
+                    Err(error) => {
+                        warn!("Can't join a LAN race: {}", error);
+                        let status = format!("Can't join: {}", error);
+                        show_lan_status(&status_query, &mut text_query, status);
+                    }
+                },
+                TitleAction::Controls => next_screen.set(Screen::Controls),
                 TitleAction::Credits => next_screen.set(Screen::Credits),
                 TitleAction::Levels => next_screen.set(Screen::Levels),
 
@@ -89,3 +176,19 @@ fn handle_title_action(
         }
     }
 }
+
+/// The session updates the status while it runs, its errors are shown here
+#[cfg(not(target_family = "wasm"))]
+fn show_lan_status(
+    status_query: &Query<&Children, With<LanStatus>>,
+    text_query: &mut Query<&mut Text>,
+    status: String,
+) {
+    for children in status_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value.clone_from(&status);
+            }
+        }
+    }
+}
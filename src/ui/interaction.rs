@@ -41,7 +41,10 @@ fn apply_interaction_palette(
     }
 }
 
-fn apply_interaction_sprite(mut commands: Commands, mut query: InteractionQuery<&mut Animation>) {
+pub(super) fn apply_interaction_sprite(
+    mut commands: Commands,
+    mut query: InteractionQuery<&mut Animation>,
+) {
     for (interaction, mut animation) in query.iter_mut() {
         match interaction {
             Interaction::None => {
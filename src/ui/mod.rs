@@ -1,6 +1,7 @@
 #![allow(dead_code, unused_imports)]
 
 pub mod interaction;
+mod navigation;
 pub mod palette;
 mod widgets;
 
@@ -15,5 +16,5 @@ pub mod prelude {
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(interaction::plugin);
+    app.add_plugins((interaction::plugin, navigation::plugin));
 }
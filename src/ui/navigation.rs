@@ -0,0 +1,118 @@
+use bevy::{prelude::*, ui::UiSystem};
+use bevy_aseprite_ultra::prelude::{Animation, AnimationRepeat};
+
+use crate::game::audio::sfx::PlaySfx;
+
+use super::{interaction::apply_interaction_sprite, widgets::DisableButton};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<MenuFocus>();
+    app.init_resource::<MenuFocus>();
+    // pressed before the menus read their buttons
+    app.add_systems(PreUpdate, navigate_with_dpad.after(UiSystem::Focus));
+    app.add_systems(Update, highlight_focus.after(apply_interaction_sprite));
+}
+
+/// Button chosen with a gamepad, pressed with its south button
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct MenuFocus {
+    pub focused: Option<Entity>,
+    /// Pressed on the last frame, released on this one
+    pressed: Option<Entity>,
+}
+
+/// Up and left go to the previous button on the screen, down and right to the next one
+fn navigate_with_dpad(
+    mut commands: Commands,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut focus: ResMut<MenuFocus>,
+    mut button_query: Query<
+        (Entity, &GlobalTransform, &mut Interaction),
+        (With<Button>, Without<DisableButton>),
+    >,
+) {
+    if let Some(pressed) = focus.pressed.take() {
+        if let Ok((.., mut interaction)) = button_query.get_mut(pressed) {
+            *interaction = Interaction::None;
+        }
+    }
+
+    // reading order, row by row
+    let mut buttons: Vec<_> = button_query
+        .iter()
+        .map(|(entity, transform, _)| (entity, transform.translation().xy()))
+        .collect();
+    buttons.sort_by(|(_, a), (_, b)| {
+        a.y.round()
+            .total_cmp(&b.y.round())
+            .then(a.x.total_cmp(&b.x))
+    });
+    if buttons.is_empty() {
+        focus.focused = None;
+        return;
+    }
+
+    let just_pressed = |button_type: GamepadButtonType| {
+        gamepad_buttons
+            .get_just_pressed()
+            .any(|button| button.button_type == button_type)
+    };
+    let step = if just_pressed(GamepadButtonType::DPadDown)
+        || just_pressed(GamepadButtonType::DPadRight)
+    {
+        1
+    } else if just_pressed(GamepadButtonType::DPadUp) || just_pressed(GamepadButtonType::DPadLeft) {
+        -1
+    } else {
+        0
+    };
+
+    let current = focus
+        .focused
+        .and_then(|focused| buttons.iter().position(|(entity, _)| *entity == focused));
+    if step != 0 {
+        let next = match current {
+            Some(current) => (current as i32 + step).rem_euclid(buttons.len() as i32) as usize,
+            None => 0,
+        };
+        focus.focused = Some(buttons[next].0);
+        commands.trigger(PlaySfx::Key("button_hovered".to_string()));
+    } else if current.is_none() {
+        // the screen changed under the focus
+        focus.focused = None;
+    }
+
+    if just_pressed(GamepadButtonType::South) {
+        if let Some(focused) = focus.focused {
+            if let Ok((.., mut interaction)) = button_query.get_mut(focused) {
+                *interaction = Interaction::Pressed;
+                focus.pressed = Some(focused);
+            }
+        }
+    }
+}
+
+/// The focused button looks hovered, the others follow the mouse
+fn highlight_focus(
+    focus: Res<MenuFocus>,
+    mut last_focused: Local<Option<Entity>>,
+    mut button_query: Query<(&Interaction, &mut Animation), With<Button>>,
+) {
+    if *last_focused != focus.focused {
+        if let Some(Ok((Interaction::None, mut animation))) =
+            last_focused.map(|entity| button_query.get_mut(entity))
+        {
+            animation.play("default", AnimationRepeat::Loop);
+        }
+        *last_focused = focus.focused;
+    }
+
+    if let Some(Ok((Interaction::None, mut animation))) =
+        focus.focused.map(|entity| button_query.get_mut(entity))
+    {
+        if animation.tag.as_deref() != Some("hovered") {
+            animation.play("hovered", AnimationRepeat::Loop);
+        }
+    }
+}